@@ -0,0 +1,17 @@
+//! Feeds arbitrary text to every parser and then to that day's solvers, using the first
+//! byte to choose which.
+//!
+//! Run with `cargo fuzz run solvers` from the `2020` directory.
+
+#![no_main]
+
+use adventofcode::fuzz::{solve, INPUTS};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((&selector, text)) = data.split_first() {
+        if let Ok(text) = std::str::from_utf8(text) {
+            let _ = solve(INPUTS[selector as usize % INPUTS.len()], text);
+        }
+    }
+});
@@ -1,66 +1,171 @@
-use std::fs::File;
-use std::io::{self, prelude::*};
-use std::num::ParseIntError;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::path::Path;
 
-pub fn problem1() -> Result<(), ParseIntError> {
-    let path = Path::new("input_day1.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
-
-    let lines = io::BufReader::new(file).lines();
-    let mut numbers = Vec::new();
-    for _line in lines {
-        if let Ok(line) = _line {
-            let number = line.parse::<u64>()?;
-            numbers.push(number);
+use crate::generate::check_size;
+use crate::ksum::KSum;
+use crate::{input, records};
+
+const TARGET: u64 = 2020;
+
+/// Which combinations of expenses to report.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Report {
+    /// The product of the first combination found.
+    First,
+    /// Every distinct combination, followed by its product.
+    All,
+    /// The number of distinct combinations.
+    Count,
+}
+
+impl std::str::FromStr for Report {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Report> {
+        match s {
+            "first" => Ok(Report::First),
+            "all" => Ok(Report::All),
+            "count" => Ok(Report::Count),
+            _ => Err(anyhow::anyhow!("unknown report `{}`", s)),
         }
     }
+}
+
+#[derive(Debug, PartialEq)]
+enum ExpenseReportError {
+    NoCombination { k: usize, target: u64 },
+    ProductOverflow { combination: Vec<u64> },
+}
 
-    for x in &numbers {
-        for y in &numbers {
-            if x + y == 2020 {
-                println!("{}", x * y);
-                return Ok(());
+impl std::error::Error for ExpenseReportError {}
+
+impl std::fmt::Display for ExpenseReportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExpenseReportError::NoCombination { k, target } => {
+                write!(f, "no {} expenses sum to {}", k, target)
+            }
+            ExpenseReportError::ProductOverflow { combination } => {
+                write!(f, "the product of {:?} overflows a u64", combination)
             }
         }
     }
+}
 
-    Ok(())
+pub fn problem1() -> anyhow::Result<u64> {
+    let expenses = read_expenses(Path::new("input_day1.txt"))?;
+    Ok(first_product(&expenses, 2, TARGET)?)
 }
 
-pub fn problem2() -> Result<(), ParseIntError> {
-    let path = Path::new("input_day1.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
-
-    let lines = io::BufReader::new(file).lines();
-    let mut numbers = Vec::new();
-    for _line in lines {
-        if let Ok(line) = _line {
-            let number = line.parse::<u64>()?;
-            numbers.push(number);
-        }
-    }
+pub fn problem2() -> anyhow::Result<u64> {
+    let expenses = read_expenses(Path::new("input_day1.txt"))?;
+    Ok(first_product(&expenses, 3, TARGET)?)
+}
+
+fn product(combination: &[u64]) -> Result<u64, ExpenseReportError> {
+    combination
+        .iter()
+        .try_fold(1u64, |product, &x| product.checked_mul(x))
+        .ok_or_else(|| ExpenseReportError::ProductOverflow {
+            combination: combination.to_vec(),
+        })
+}
+
+/// The product of the first `k` expenses found which sum to `target`.
+fn first_product(expenses: &[u64], k: usize, target: u64) -> Result<u64, ExpenseReportError> {
+    let combination = KSum::new(expenses, k, target)
+        .first()
+        .ok_or(ExpenseReportError::NoCombination { k, target })?;
+    product(&combination)
+}
+
+/// Finds `k` expenses which sum to `target` and prints the requested report.
+pub fn solve(target: u64, k: usize, report: Report) -> anyhow::Result<()> {
+    let expenses = read_expenses(Path::new("input_day1.txt"))?;
 
-    for x in &numbers {
-        for y in &numbers {
-            for z in &numbers {
-                if x + y + z == 2020 {
-                    println!("{}", x * y * z);
-                    return Ok(());
-                }
+    match report {
+        Report::First => println!("{}", first_product(&expenses, k, target)?),
+        Report::All => {
+            for combination in KSum::new(&expenses, k, target).all() {
+                let values = combination
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>();
+                println!("{} {}", values.join(" "), product(&combination)?);
             }
         }
+        Report::Count => println!("{}", KSum::new(&expenses, k, target).count()),
     }
 
     Ok(())
 }
+
+/// A random expense report of `size` entries, with at least one pair and one triple of
+/// entries summing to 2020.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 5..=100_000)?;
+
+    let mut expenses = (0..size - 5)
+        .map(|_| rng.gen_range(1..TARGET))
+        .collect::<Vec<_>>();
+    let a = rng.gen_range(1..TARGET);
+    let b = rng.gen_range(1..TARGET - 1);
+    let c = rng.gen_range(1..TARGET - b);
+    expenses.extend([a, TARGET - a, b, c, TARGET - b - c]);
+    expenses.shuffle(rng);
+
+    Ok(expenses.iter().map(|x| format!("{}\n", x)).collect())
+}
+
+fn read_expenses(path: &Path) -> anyhow::Result<Vec<u64>> {
+    let expenses = records::parse_numbers(1, &input::read_lines(path)?)?;
+    Ok(expenses)
+}
+
+/// Checks that `lines` parse as an expense report.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    records::parse_numbers::<u64, _>(1, lines)?;
+    Ok(())
+}
+
+/// Solves both parts of the puzzle for the expense report in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let expenses = records::parse_numbers(1, lines)?;
+    let first = first_product(&expenses, 2, TARGET);
+    let second = first_product(&expenses, 3, TARGET);
+    first?;
+    second?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example from the puzzle.
+    const EXAMPLE: [u64; 6] = [1721, 979, 366, 299, 675, 1456];
+
+    #[test]
+    fn test_first_product() {
+        assert_eq!(first_product(&EXAMPLE, 2, TARGET), Ok(514579));
+        assert_eq!(first_product(&EXAMPLE, 3, TARGET), Ok(241861950));
+        assert_eq!(
+            first_product(&EXAMPLE, 2, 5),
+            Err(ExpenseReportError::NoCombination { k: 2, target: 5 })
+        );
+    }
+
+    #[test]
+    fn test_product_overflow() {
+        let expenses = [1 << 40, 1 << 40, 7];
+        assert_eq!(
+            first_product(&expenses, 2, 1 << 41),
+            Err(ExpenseReportError::ProductOverflow {
+                combination: vec![1 << 40, 1 << 40]
+            })
+        );
+        assert_eq!(product(&[1 << 31, 1 << 32]), Ok(1 << 63));
+    }
+}
@@ -1,8 +1,15 @@
-use anyhow::Result;
-use std::fs::File;
-use std::io::{self, prelude::*};
+use num_bigint::{BigUint, RandBigInt};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use std::path::Path;
 
+use crate::generate::check_size;
+use crate::{input, records};
+
+/// The joltage differences which adapters can tolerate in the puzzle.
+const ALLOWED_GAPS: [u64; 3] = [1, 2, 3];
+
 #[derive(Debug)]
 struct ProblemInvariantError;
 
@@ -17,89 +24,381 @@ impl std::fmt::Display for ProblemInvariantError {
     }
 }
 
-pub fn problem1() -> anyhow::Result<()> {
-    let path = Path::new("input_day10.txt");
-    let display = path.display();
+#[derive(Debug, PartialEq)]
+enum AdapterChainsError {
+    NoAllowedGaps,
+    DeviceJoltageOverflow,
+}
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+impl std::error::Error for AdapterChainsError {}
+
+impl std::fmt::Display for AdapterChainsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AdapterChainsError::NoAllowedGaps => {
+                write!(f, "at least one joltage gap must be allowed")
+            }
+            AdapterChainsError::DeviceJoltageOverflow => {
+                write!(f, "the device's joltage does not fit in a u64")
+            }
+        }
+    }
+}
+
+/// Every way of chaining a set of adapters from the charging outlet to the device.
+///
+/// A chain starts at the outlet (0 jolts), uses adapters in increasing order of joltage and
+/// ends at the device, which is rated the largest allowed gap above the highest adapter, as
+/// the puzzle's device is rated 3 jolts above it. Each step of the chain must increase the
+/// joltage by one of the allowed gaps.
+#[derive(Debug)]
+struct AdapterChains {
+    /// The outlet, the adapters and the device, in increasing order of joltage.
+    joltages: Vec<u64>,
+    allowed_gaps: Vec<u64>,
+    max_gap: u64,
+    /// The number of chains from each joltage to the device.
+    counts: Vec<BigUint>,
+}
+
+impl AdapterChains {
+    fn new(adapters: &[u64], allowed_gaps: &[u64]) -> Result<AdapterChains, AdapterChainsError> {
+        let max_gap = *allowed_gaps
+            .iter()
+            .max()
+            .ok_or(AdapterChainsError::NoAllowedGaps)?;
+
+        let mut joltages = adapters.to_vec();
+        joltages.push(0);
+        joltages.sort_unstable();
+        let device = joltages[joltages.len() - 1]
+            .checked_add(max_gap)
+            .ok_or(AdapterChainsError::DeviceJoltageOverflow)?;
+        joltages.push(device);
+
+        let mut counts = vec![BigUint::from(0u32); joltages.len()];
+        counts[joltages.len() - 1] = BigUint::from(1u32);
+        for i in (0..joltages.len() - 1).rev() {
+            let mut count = BigUint::from(0u32);
+            for j in i + 1..joltages.len() {
+                let gap = joltages[j] - joltages[i];
+                if gap > max_gap {
+                    break;
+                }
+                if allowed_gaps.contains(&gap) {
+                    count += &counts[j];
+                }
+            }
+            counts[i] = count;
+        }
+
+        Ok(AdapterChains {
+            joltages,
+            allowed_gaps: allowed_gaps.to_vec(),
+            max_gap,
+            counts,
+        })
+    }
+
+    /// The number of distinct chains.
+    fn count(&self) -> &BigUint {
+        &self.counts[0]
+    }
 
-    let mut numbers = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .map(|x| x.parse::<u64>())
-        .collect::<Result<Vec<_>, _>>()?;
-    numbers.push(0);
-    numbers.sort_unstable();
-    numbers.push(numbers.last().unwrap() + 3);
-
-    let differences = numbers
+    /// The indices of the joltages which can follow joltage `i` on a chain to the device.
+    fn successors(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        (i + 1..self.joltages.len())
+            .take_while(move |&j| self.joltages[j] - self.joltages[i] <= self.max_gap)
+            .filter(move |&j| {
+                self.allowed_gaps
+                    .contains(&(self.joltages[j] - self.joltages[i]))
+                    && self.counts[j] > BigUint::from(0u32)
+            })
+    }
+
+    /// Lists up to `limit` chains, in lexicographic order of joltages.
+    ///
+    /// The search keeps its own stack, holding each joltage on the current path alongside the
+    /// successors of it still to try, so that long chains cannot overflow the call stack.
+    fn enumerate(&self, limit: usize) -> Vec<Vec<u64>> {
+        let mut chains = Vec::new();
+        let device = self.joltages.len() - 1;
+        let mut stack = vec![(0, self.successors(0))];
+        while chains.len() < limit {
+            let (i, successors) = match stack.last_mut() {
+                Some(top) => top,
+                None => break,
+            };
+            if *i == device {
+                chains.push(stack.iter().map(|&(j, _)| self.joltages[j]).collect());
+                stack.pop();
+            } else if let Some(j) = successors.next() {
+                stack.push((j, self.successors(j)));
+            } else {
+                stack.pop();
+            }
+        }
+        chains
+    }
+
+    /// Finds the chain at position `index` in the order produced by `enumerate`.
+    fn nth(&self, index: &BigUint) -> Option<Vec<u64>> {
+        if index >= self.count() {
+            return None;
+        }
+
+        let mut index = index.clone();
+        let mut i = 0;
+        let mut chain = vec![self.joltages[0]];
+        while i != self.joltages.len() - 1 {
+            for j in self.successors(i) {
+                if index < self.counts[j] {
+                    i = j;
+                    break;
+                }
+                index -= &self.counts[j];
+            }
+            chain.push(self.joltages[i]);
+        }
+
+        Some(chain)
+    }
+
+    /// Draws `n` chains uniformly at random, reproducibly for a given seed.
+    fn sample(&self, n: usize, seed: u64) -> Vec<Vec<u64>> {
+        if self.count() == &BigUint::from(0u32) {
+            return Vec::new();
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| {
+                let index = rng.gen_biguint_below(self.count());
+                self.nth(&index).unwrap()
+            })
+            .collect()
+    }
+}
+
+pub fn problem1() -> anyhow::Result<usize> {
+    let adapters = read_adapters(Path::new("input_day10.txt"))?;
+    Ok(gap_product(&AdapterChains::new(&adapters, &ALLOWED_GAPS)?))
+}
+
+pub fn problem2() -> anyhow::Result<BigUint> {
+    let adapters = read_adapters(Path::new("input_day10.txt"))?;
+    Ok(count_chains(&AdapterChains::new(
+        &adapters,
+        &ALLOWED_GAPS,
+    )?)?)
+}
+
+/// The number of gaps of 1 jolt times the number of gaps of 3 jolts when every adapter is
+/// chained together.
+fn gap_product(chains: &AdapterChains) -> usize {
+    let differences = chains
+        .joltages
         .windows(2)
-        .into_iter()
         .map(|xs| xs[1] - xs[0])
         .collect::<Vec<_>>();
 
     let ones = differences.iter().filter(|&&x| x == 1).count();
     let threes = differences.iter().filter(|&&x| x == 3).count();
-    println!("{}", ones * threes);
+    ones * threes
+}
 
-    Ok(())
+fn count_chains(chains: &AdapterChains) -> Result<BigUint, ProblemInvariantError> {
+    if chains.count() == &BigUint::from(0u32) {
+        return Err(ProblemInvariantError);
+    }
+    Ok(chains.count().clone())
 }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day10.txt");
-    let display = path.display();
+/// Prints the number of adapter chains for the adapters at `path`, followed by up to `list`
+/// of the chains in order and `sample` chains drawn at random using `seed`.
+pub fn arrangements(
+    path: &Path,
+    allowed_gaps: Option<&[u64]>,
+    list: usize,
+    sample: usize,
+    seed: u64,
+) -> anyhow::Result<()> {
+    let adapters = read_adapters(path)?;
+    let chains = AdapterChains::new(&adapters, allowed_gaps.unwrap_or(&ALLOWED_GAPS))?;
+    println!("{}", chains.count());
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
+    let format_chain = |chain: &Vec<u64>| {
+        chain
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
     };
+    for chain in chains.enumerate(list) {
+        println!("{}", format_chain(&chain));
+    }
+    for chain in chains.sample(sample, seed) {
+        println!("{}", format_chain(&chain));
+    }
 
-    let mut numbers = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .map(|x| x.parse::<u64>())
-        .collect::<Result<Vec<_>, _>>()?;
-    numbers.push(0);
-    numbers.sort_unstable();
-    numbers.push(numbers.last().unwrap() + 3);
-
-    let differences = numbers
-        .windows(2)
-        .into_iter()
-        .map(|xs| xs[1] - xs[0])
-        .collect::<Vec<_>>();
+    Ok(())
+}
 
-    assert!(!differences.contains(&2));
-    let split_by_threes = differences
-        .as_slice()
-        .split(|&x| x == 3)
-        .collect::<Vec<_>>();
+/// `size` random adapters, which can all be chained together.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=100_000)?;
 
-    let arrangements_per_group = split_by_threes
-        .iter()
-        .map(|xs| xs.iter().count())
-        .map(arrangements)
-        .collect::<Vec<_>>();
+    let mut joltage = 0;
+    let mut adapters = (0..size)
+        .map(|_| {
+            joltage += [1, 1, 1, 2, 3, 3].choose(rng).unwrap();
+            joltage
+        })
+        .collect::<Vec<u64>>();
+    adapters.shuffle(rng);
 
-    let total_arrangements: u128 = arrangements_per_group.iter().map(|&x| x as u128).product();
-    println!("{}", total_arrangements);
+    Ok(adapters.iter().map(|x| format!("{}\n", x)).collect())
+}
 
+fn read_adapters(path: &Path) -> anyhow::Result<Vec<u64>> {
+    let numbers = records::parse_numbers(1, &input::read_lines(path)?)?;
+    Ok(numbers)
+}
+
+/// Checks that `lines` parse as a list of adapters.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    records::parse_numbers::<u64, _>(1, lines)?;
     Ok(())
 }
 
-fn arrangements(n: usize) -> usize {
-    // TODO: Find a closed form that represents these values.
-    match n {
-        0 => 1,
-        1 => 1,
-        2 => 2,
-        3 => 4,
-        4 => 7,
-        _ => panic!(),
+/// Solves both parts of the puzzle for the adapters in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let adapters = records::parse_numbers(1, lines)?;
+    let chains = AdapterChains::new(&adapters, &ALLOWED_GAPS)?;
+    gap_product(&chains);
+    count_chains(&chains)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first example from the puzzle.
+    const EXAMPLE: [u64; 11] = [16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+
+    /// Tries every subset of the adapters in turn, keeping those which form a chain.
+    fn brute_force_chains(adapters: &[u64], allowed_gaps: &[u64]) -> Vec<Vec<u64>> {
+        let mut adapters = adapters.to_vec();
+        adapters.sort_unstable();
+        let device = adapters.last().unwrap_or(&0) + allowed_gaps.iter().max().unwrap();
+        let mut chains = (0..1u32 << adapters.len())
+            .map(|subset| {
+                let mut chain = vec![0];
+                chain.extend(
+                    (0..adapters.len())
+                        .filter(|i| subset >> i & 1 == 1)
+                        .map(|i| adapters[i]),
+                );
+                chain.push(device);
+                chain
+            })
+            .filter(|chain| {
+                chain
+                    .windows(2)
+                    .all(|w| allowed_gaps.contains(&(w[1] - w[0])))
+            })
+            .collect::<Vec<_>>();
+        chains.sort();
+        chains
+    }
+
+    #[test]
+    fn test_count() {
+        let chains = AdapterChains::new(&EXAMPLE, &ALLOWED_GAPS).unwrap();
+        assert_eq!(chains.count(), &BigUint::from(8u32));
+
+        let example2 = [
+            28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35,
+            8, 17, 7, 9, 4, 2, 34, 10, 3,
+        ];
+        let chains = AdapterChains::new(&example2, &ALLOWED_GAPS).unwrap();
+        assert_eq!(chains.count(), &BigUint::from(19208u32));
+    }
+
+    #[test]
+    fn test_custom_gaps() {
+        // The device is rated 2 jolts above the highest adapter when only gaps of 1 and 2
+        // are allowed.
+        let chains = AdapterChains::new(&[1, 2, 3], &[1, 2]).unwrap();
+        assert_eq!(chains.count(), &BigUint::from(3u32));
+        assert_eq!(
+            chains.enumerate(10),
+            vec![vec![0, 1, 2, 3, 5], vec![0, 1, 3, 5], vec![0, 2, 3, 5]]
+        );
+
+        for allowed_gaps in [&[1][..], &[2], &[1, 3], &[2, 3], &[1, 2, 3, 4]] {
+            let adapters = [1, 2, 4, 5, 6, 9, 10, 11, 13];
+            let chains = AdapterChains::new(&adapters, allowed_gaps).unwrap();
+            let expected = brute_force_chains(&adapters, allowed_gaps);
+            assert_eq!(chains.count(), &BigUint::from(expected.len()));
+            assert_eq!(chains.enumerate(usize::MAX), expected);
+        }
+    }
+
+    #[test]
+    fn test_invalid_chains() {
+        assert_eq!(
+            AdapterChains::new(&EXAMPLE, &[]).unwrap_err(),
+            AdapterChainsError::NoAllowedGaps
+        );
+        assert_eq!(
+            AdapterChains::new(&[u64::MAX - 1], &ALLOWED_GAPS).unwrap_err(),
+            AdapterChainsError::DeviceJoltageOverflow
+        );
+
+        // Without any adapters, the outlet connects straight to the device.
+        let chains = AdapterChains::new(&[], &ALLOWED_GAPS).unwrap();
+        assert_eq!(chains.enumerate(10), vec![vec![0, 3]]);
+    }
+
+    #[test]
+    fn test_enumerate() {
+        let chains = AdapterChains::new(&EXAMPLE, &ALLOWED_GAPS).unwrap();
+        let all = chains.enumerate(usize::MAX);
+        assert_eq!(all, brute_force_chains(&EXAMPLE, &ALLOWED_GAPS));
+        assert_eq!(chains.enumerate(3), all[..3]);
+        assert!(chains.enumerate(0).is_empty());
+        for (index, chain) in all.iter().enumerate() {
+            assert_eq!(chains.nth(&BigUint::from(index)).as_ref(), Some(chain));
+        }
+        assert_eq!(chains.nth(&BigUint::from(all.len())), None);
+    }
+
+    #[test]
+    fn test_enumerate_long_chain() {
+        let adapters = (1..=200_000).collect::<Vec<_>>();
+        let chains = AdapterChains::new(&adapters, &[1]).unwrap();
+        let first = chains.enumerate(1);
+        assert_eq!(first[0].len(), 200_002);
+        assert_eq!(first[0][200_000], 200_000);
+    }
+
+    #[test]
+    fn test_sample() {
+        let chains = AdapterChains::new(&EXAMPLE, &ALLOWED_GAPS).unwrap();
+        let all = chains.enumerate(usize::MAX);
+        let sample = chains.sample(50, 7);
+        assert_eq!(sample.len(), 50);
+        assert!(sample.iter().all(|chain| all.contains(chain)));
+        assert_eq!(chains.sample(50, 7), sample);
+
+        // Every chain turns up in a large enough sample.
+        let sample = chains.sample(500, 1);
+        assert!(all.iter().all(|chain| sample.contains(chain)));
+
+        let chains = AdapterChains::new(&[1, 5], &ALLOWED_GAPS).unwrap();
+        assert_eq!(chains.count(), &BigUint::from(0u32));
+        assert!(chains.sample(5, 0).is_empty());
     }
 }
@@ -1,51 +1,78 @@
-use anyhow::Result;
-use std::fs::File;
-use std::io::{self, prelude::*};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashMap;
 use std::path::Path;
 
+use crate::generate::check_size;
+use crate::input;
+
 #[derive(Debug)]
-struct ProblemInvariantError;
+struct ParseLayoutError {
+    line: usize,
+    character: char,
+}
+
+impl std::error::Error for ParseLayoutError {}
+
+impl std::fmt::Display for ParseLayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: expected `.`, `L` or `#`, found `{}`",
+            self.line, self.character
+        )
+    }
+}
+
+#[derive(Debug)]
+struct CycleError {
+    period: usize,
+}
 
-impl std::error::Error for ProblemInvariantError {}
+impl std::error::Error for CycleError {}
 
-impl std::fmt::Display for ProblemInvariantError {
+impl std::fmt::Display for CycleError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "An invariant of the Advent of Code challenge seems to have been violated."
+            "The seat layout never settles, repeating every {} rounds.",
+            self.period
         )
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 enum Position {
     Floor,
     Empty,
     Occupied,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Ferry {
     seats: Vec<Vec<Position>>,
 }
 
 impl Ferry {
-    fn new(lines: &[&str]) -> Ferry {
-        Ferry {
-            seats: lines
-                .iter()
-                .map(|row| {
-                    row.chars()
-                        .map(|c| match c {
-                            '.' => Position::Floor,
-                            'L' => Position::Empty,
-                            '#' => Position::Occupied,
-                            _ => panic!("Unexpected character."),
-                        })
-                        .collect()
-                })
-                .collect(),
-        }
+    fn new(lines: &[&str]) -> Result<Ferry, ParseLayoutError> {
+        let seats = lines
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                row.chars()
+                    .map(|c| match c {
+                        '.' => Ok(Position::Floor),
+                        'L' => Ok(Position::Empty),
+                        '#' => Ok(Position::Occupied),
+                        _ => Err(ParseLayoutError {
+                            line: i + 1,
+                            character: c,
+                        }),
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Ferry { seats })
     }
 
     fn next1(&self) -> Ferry {
@@ -119,29 +146,29 @@ impl Ferry {
     }
 
     fn neighbors1(&self, x: usize, y: usize) -> Vec<Option<&Position>> {
-        let mut result = Vec::with_capacity(8);
-        result.push(get_cell(&self.seats, x, y, -1, -1));
-        result.push(get_cell(&self.seats, x, y, -1, 0));
-        result.push(get_cell(&self.seats, x, y, -1, 1));
-        result.push(get_cell(&self.seats, x, y, 0, -1));
-        result.push(get_cell(&self.seats, x, y, 0, 1));
-        result.push(get_cell(&self.seats, x, y, 1, -1));
-        result.push(get_cell(&self.seats, x, y, 1, 0));
-        result.push(get_cell(&self.seats, x, y, 1, 1));
-        result
+        vec![
+            get_cell(&self.seats, x, y, -1, -1),
+            get_cell(&self.seats, x, y, -1, 0),
+            get_cell(&self.seats, x, y, -1, 1),
+            get_cell(&self.seats, x, y, 0, -1),
+            get_cell(&self.seats, x, y, 0, 1),
+            get_cell(&self.seats, x, y, 1, -1),
+            get_cell(&self.seats, x, y, 1, 0),
+            get_cell(&self.seats, x, y, 1, 1),
+        ]
     }
 
     fn neighbors2(&self, x: usize, y: usize) -> Vec<Option<&Position>> {
-        let mut result = Vec::with_capacity(8);
-        result.push(get_non_floor_cell_in_direction(&self.seats, x, y, -1, -1));
-        result.push(get_non_floor_cell_in_direction(&self.seats, x, y, -1, 0));
-        result.push(get_non_floor_cell_in_direction(&self.seats, x, y, -1, 1));
-        result.push(get_non_floor_cell_in_direction(&self.seats, x, y, 0, -1));
-        result.push(get_non_floor_cell_in_direction(&self.seats, x, y, 0, 1));
-        result.push(get_non_floor_cell_in_direction(&self.seats, x, y, 1, -1));
-        result.push(get_non_floor_cell_in_direction(&self.seats, x, y, 1, 0));
-        result.push(get_non_floor_cell_in_direction(&self.seats, x, y, 1, 1));
-        result
+        vec![
+            get_non_floor_cell_in_direction(&self.seats, x, y, -1, -1),
+            get_non_floor_cell_in_direction(&self.seats, x, y, -1, 0),
+            get_non_floor_cell_in_direction(&self.seats, x, y, -1, 1),
+            get_non_floor_cell_in_direction(&self.seats, x, y, 0, -1),
+            get_non_floor_cell_in_direction(&self.seats, x, y, 0, 1),
+            get_non_floor_cell_in_direction(&self.seats, x, y, 1, -1),
+            get_non_floor_cell_in_direction(&self.seats, x, y, 1, 0),
+            get_non_floor_cell_in_direction(&self.seats, x, y, 1, 1),
+        ]
     }
 }
 
@@ -172,79 +199,130 @@ fn get_non_floor_cell_in_direction(
     x_offset: isize,
     y_offset: isize,
 ) -> Option<&Position> {
-    let mut n = 1;
-    loop {
-        let seat = get_cell(seats, x, y, x_offset * n, y_offset * n);
-        if seat != Some(&Position::Floor) {
-            return seat;
-        } else {
-            n += 1;
-        }
-    }
+    // No line of sight is longer than the layout, even when its rows have different
+    // lengths, so this always stops, whatever the offsets.
+    let max_distance = seats.iter().map(Vec::len).fold(seats.len(), usize::max) as isize;
+    (1..=max_distance)
+        .map(|n| get_cell(seats, x, y, x_offset * n, y_offset * n))
+        .find(|&seat| seat != Some(&Position::Floor))
+        .flatten()
 }
 
-pub fn problem1() -> anyhow::Result<()> {
+pub fn problem1() -> anyhow::Result<usize> {
     let path = Path::new("input_day11.txt");
-    let display = path.display();
+    let lines = input::read_lines(path)?;
+
+    let ferry = Ferry::new(&lines.iter().map(AsRef::as_ref).collect::<Vec<_>>())?;
+    Ok(count_occupied(&settle(ferry, Ferry::next1)?))
+}
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+pub fn problem2() -> anyhow::Result<usize> {
+    let path = Path::new("input_day11.txt");
+    let lines = input::read_lines(path)?;
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+    let ferry = Ferry::new(&lines.iter().map(AsRef::as_ref).collect::<Vec<_>>())?;
+    Ok(count_occupied(&settle(ferry, Ferry::next2)?))
+}
 
-    let mut ferry = Ferry::new(&lines.iter().map(AsRef::as_ref).collect::<Vec<_>>());
-    loop {
-        let next = ferry.next1();
-        if ferry == next {
+fn count_occupied(ferry: &Ferry) -> usize {
+    ferry
+        .seats
+        .iter()
+        .map(|row| row.iter().filter(|s| **s == Position::Occupied).count())
+        .sum()
+}
+
+/// Repeatedly applies `next` to `ferry` until the layout stops changing, failing if it
+/// returns to an earlier layout instead, as random layouts sometimes do.
+fn settle(mut ferry: Ferry, next: fn(&Ferry) -> Ferry) -> Result<Ferry, CycleError> {
+    let mut seen = HashMap::new();
+    for round in 0.. {
+        if let Some(previous) = seen.insert(ferry.clone(), round) {
+            return Err(CycleError {
+                period: round - previous,
+            });
+        }
+        let next = next(&ferry);
+        if next == ferry {
             break;
         }
         ferry = next;
     }
+    Ok(ferry)
+}
 
-    let occupied_seats: usize = ferry
-        .seats
-        .iter()
-        .map(|row| row.iter().filter(|s| **s == Position::Occupied).count())
-        .sum();
+/// A random seat layout `size` positions square, on which both sets of rules eventually
+/// stop changing.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=200)?;
 
-    println!("{}", occupied_seats);
+    loop {
+        let rows = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| if rng.gen_bool(0.8) { 'L' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        let rows = rows.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+        if settle(Ferry::new(&rows)?, Ferry::next1).is_ok()
+            && settle(Ferry::new(&rows)?, Ferry::next2).is_ok()
+        {
+            return Ok(rows.iter().map(|row| format!("{}\n", row)).collect());
+        }
+    }
+}
 
+/// Checks that `lines` parse as a seat layout.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    Ferry::new(lines)?;
     Ok(())
 }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day11.txt");
-    let display = path.display();
+/// Solves both parts of the puzzle for the seat layout in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let first = settle(Ferry::new(lines)?, Ferry::next1);
+    let second = settle(Ferry::new(lines)?, Ferry::next2);
+    first?;
+    second?;
+    Ok(())
+}
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+    const EXAMPLE: [&str; 10] = [
+        "L.LL.LL.LL",
+        "LLLLLLL.LL",
+        "L.L.L..L..",
+        "LLLL.LL.LL",
+        "L.LL.LL.LL",
+        "L.LLLLL.LL",
+        "..L.L.....",
+        "LLLLLLLLLL",
+        "L.LLLLLL.L",
+        "L.LLLLL.LL",
+    ];
 
-    let mut ferry = Ferry::new(&lines.iter().map(AsRef::as_ref).collect::<Vec<_>>());
-    loop {
-        let next = ferry.next2();
-        if ferry == next {
-            break;
-        }
-        ferry = next;
+    #[test]
+    fn test_settle() {
+        let ferry = Ferry::new(&EXAMPLE).unwrap();
+        assert_eq!(
+            count_occupied(&settle(ferry.clone(), Ferry::next1).unwrap()),
+            37
+        );
+        assert_eq!(count_occupied(&settle(ferry, Ferry::next2).unwrap()), 26);
     }
 
-    let occupied_seats: usize = ferry
-        .seats
-        .iter()
-        .map(|row| row.iter().filter(|s| **s == Position::Occupied).count())
-        .sum();
-
-    println!("{}", occupied_seats);
-
-    Ok(())
+    #[test]
+    fn test_layout_which_never_settles() {
+        let rows = [".LL..L", "LLLLLL", "LLLLL.", ".LL..L", "LLLLLL", "LLLLLL"];
+        let error = settle(Ferry::new(&rows).unwrap(), Ferry::next1).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The seat layout never settles, repeating every 2 rounds."
+        );
+        assert!(check_solutions(&rows).is_err());
+    }
 }
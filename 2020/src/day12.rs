@@ -1,9 +1,13 @@
-use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::convert::TryFrom;
-use std::fs::File;
-use std::io::{self, prelude::*};
 use std::path::Path;
 
+use crate::generate::check_size;
+use crate::input;
+use crate::parser::{self, one_of, pair, unsigned, ParseError};
+
 #[derive(Debug)]
 struct ProblemInvariantError;
 
@@ -18,6 +22,63 @@ impl std::fmt::Display for ProblemInvariantError {
     }
 }
 
+#[derive(Debug)]
+enum ParseNavigationError {
+    Syntax { line: usize, source: ParseError },
+    Turn { line: usize, degrees: u32 },
+    TooLarge { line: usize, value: u32 },
+}
+
+impl std::error::Error for ParseNavigationError {}
+
+impl std::fmt::Display for ParseNavigationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseNavigationError::Syntax { line, source } => write!(f, "line {}: {}", line, source),
+            ParseNavigationError::Turn { line, degrees } => write!(
+                f,
+                "line {}: turns must be a multiple of 90 degrees, found {}",
+                line, degrees
+            ),
+            ParseNavigationError::TooLarge { line, value } => write!(
+                f,
+                "line {}: distances and angles must be at most {}, found {}",
+                line,
+                i32::MAX,
+                value
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum NavigationError {
+    TooFar { line: usize },
+    Distance,
+}
+
+impl std::error::Error for NavigationError {}
+
+impl std::fmt::Display for NavigationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NavigationError::TooFar { line } => {
+                write!(f, "line {}: the ship goes too far to keep track of", line)
+            }
+            NavigationError::Distance => {
+                write!(f, "the ship ends up too far away to measure the distance")
+            }
+        }
+    }
+}
+
+/// An action and how far to move or, for `L` and `R`, how many degrees to turn.
+#[derive(Debug, PartialEq)]
+struct NavigationInstruction<'a> {
+    action: &'a str,
+    value: u32,
+}
+
 #[derive(Debug)]
 enum Orientation {
     North,
@@ -47,25 +108,30 @@ impl Default for Ship {
     }
 }
 
+/// Every move returns `None` if the ship or its waypoint would end up too far away to track.
 impl Ship {
-    fn north(&mut self, magnitude: u32) {
-        self.pos_y += i32::try_from(magnitude).unwrap();
+    fn north(&mut self, magnitude: u32) -> Option<()> {
+        self.pos_y = self.pos_y.checked_add(i32::try_from(magnitude).ok()?)?;
+        Some(())
     }
 
-    fn east(&mut self, magnitude: u32) {
-        self.pos_x += i32::try_from(magnitude).unwrap();
+    fn east(&mut self, magnitude: u32) -> Option<()> {
+        self.pos_x = self.pos_x.checked_add(i32::try_from(magnitude).ok()?)?;
+        Some(())
     }
 
-    fn south(&mut self, magnitude: u32) {
-        self.pos_y -= i32::try_from(magnitude).unwrap();
+    fn south(&mut self, magnitude: u32) -> Option<()> {
+        self.pos_y = self.pos_y.checked_sub(i32::try_from(magnitude).ok()?)?;
+        Some(())
     }
 
-    fn west(&mut self, magnitude: u32) {
-        self.pos_x -= i32::try_from(magnitude).unwrap();
+    fn west(&mut self, magnitude: u32) -> Option<()> {
+        self.pos_x = self.pos_x.checked_sub(i32::try_from(magnitude).ok()?)?;
+        Some(())
     }
 
-    fn left(&mut self, magnitude: u32) {
-        let turns = magnitude / 90;
+    fn left(&mut self, magnitude: u32) -> Option<()> {
+        let turns = magnitude / 90 % 4;
         for _ in 0..turns {
             self.dir = match self.dir {
                 Orientation::North => Orientation::West,
@@ -74,10 +140,11 @@ impl Ship {
                 Orientation::West => Orientation::South,
             }
         }
+        Some(())
     }
 
-    fn right(&mut self, magnitude: u32) {
-        let turns = magnitude / 90;
+    fn right(&mut self, magnitude: u32) -> Option<()> {
+        let turns = magnitude / 90 % 4;
         for _ in 0..turns {
             self.dir = match self.dir {
                 Orientation::North => Orientation::East,
@@ -86,9 +153,10 @@ impl Ship {
                 Orientation::West => Orientation::North,
             }
         }
+        Some(())
     }
 
-    fn forward(&mut self, magnitude: u32) {
+    fn forward(&mut self, magnitude: u32) -> Option<()> {
         match self.dir {
             Orientation::North => self.north(magnitude),
             Orientation::East => self.east(magnitude),
@@ -97,125 +165,276 @@ impl Ship {
         }
     }
 
-    fn waypoint_north(&mut self, magnitude: u32) {
-        self.waypoint_y += i32::try_from(magnitude).unwrap();
+    fn waypoint_north(&mut self, magnitude: u32) -> Option<()> {
+        self.waypoint_y = self
+            .waypoint_y
+            .checked_add(i32::try_from(magnitude).ok()?)?;
+        Some(())
     }
 
-    fn waypoint_east(&mut self, magnitude: u32) {
-        self.waypoint_x += i32::try_from(magnitude).unwrap();
+    fn waypoint_east(&mut self, magnitude: u32) -> Option<()> {
+        self.waypoint_x = self
+            .waypoint_x
+            .checked_add(i32::try_from(magnitude).ok()?)?;
+        Some(())
     }
 
-    fn waypoint_south(&mut self, magnitude: u32) {
-        self.waypoint_y -= i32::try_from(magnitude).unwrap();
+    fn waypoint_south(&mut self, magnitude: u32) -> Option<()> {
+        self.waypoint_y = self
+            .waypoint_y
+            .checked_sub(i32::try_from(magnitude).ok()?)?;
+        Some(())
     }
 
-    fn waypoint_west(&mut self, magnitude: u32) {
-        self.waypoint_x -= i32::try_from(magnitude).unwrap();
+    fn waypoint_west(&mut self, magnitude: u32) -> Option<()> {
+        self.waypoint_x = self
+            .waypoint_x
+            .checked_sub(i32::try_from(magnitude).ok()?)?;
+        Some(())
     }
 
-    fn waypoint_left(&mut self, magnitude: u32) {
-        let turns = magnitude / 90;
+    fn waypoint_left(&mut self, magnitude: u32) -> Option<()> {
+        let turns = magnitude / 90 % 4;
         for _ in 0..turns {
             let x = self.waypoint_x;
             let y = self.waypoint_y;
-            self.waypoint_x = -y;
+            self.waypoint_x = y.checked_neg()?;
             self.waypoint_y = x;
         }
+        Some(())
     }
 
-    fn waypoint_right(&mut self, magnitude: u32) {
-        let turns = magnitude / 90;
+    fn waypoint_right(&mut self, magnitude: u32) -> Option<()> {
+        let turns = magnitude / 90 % 4;
         for _ in 0..turns {
             let x = self.waypoint_x;
             let y = self.waypoint_y;
             self.waypoint_x = y;
-            self.waypoint_y = -x;
+            self.waypoint_y = x.checked_neg()?;
         }
+        Some(())
+    }
+
+    fn forward_towards_waypoint(&mut self, magnitude: u32) -> Option<()> {
+        let signed_magnitude = i32::try_from(magnitude).ok()?;
+        let x = self
+            .pos_x
+            .checked_add(self.waypoint_x.checked_mul(signed_magnitude)?)?;
+        let y = self
+            .pos_y
+            .checked_add(self.waypoint_y.checked_mul(signed_magnitude)?)?;
+        self.pos_x = x;
+        self.pos_y = y;
+        Some(())
     }
 
-    fn forward_towards_waypoint(&mut self, magnitude: u32) {
-        let signed_magnitude = i32::try_from(magnitude).unwrap();
-        self.pos_x += self.waypoint_x * signed_magnitude;
-        self.pos_y += self.waypoint_y * signed_magnitude;
+    /// The Manhattan distance from the starting point.
+    fn distance(&self) -> Option<i32> {
+        self.pos_x
+            .checked_abs()?
+            .checked_add(self.pos_y.checked_abs()?)
     }
 }
 
-pub fn problem1() -> anyhow::Result<()> {
+pub fn problem1() -> anyhow::Result<i32> {
     let path = Path::new("input_day12.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+    let lines = input::read_lines(path)?;
+    navigate(&parse_navigation_instructions(&lines)?)
+}
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+pub fn problem2() -> anyhow::Result<i32> {
+    let path = Path::new("input_day12.txt");
+    let lines = input::read_lines(path)?;
+    navigate_by_waypoint(&parse_navigation_instructions(&lines)?)
+}
 
+/// The distance the ship ends up from where it started, moving itself as instructed.
+fn navigate(instructions: &[NavigationInstruction]) -> anyhow::Result<i32> {
     let mut ship = Ship::default();
 
-    for line in &lines {
-        if line.len() < 2 {
-            return Err(ProblemInvariantError.into());
-        }
-
-        let command = line.chars().next().unwrap();
-        let magnitude = line[1..].parse::<u32>()?;
-
-        match command {
-            'N' => ship.north(magnitude),
-            'E' => ship.east(magnitude),
-            'S' => ship.south(magnitude),
-            'W' => ship.west(magnitude),
-            'L' => ship.left(magnitude),
-            'R' => ship.right(magnitude),
-            'F' => ship.forward(magnitude),
+    for (i, instruction) in instructions.iter().enumerate() {
+        let magnitude = instruction.value;
+        let moved = match instruction.action {
+            "N" => ship.north(magnitude),
+            "E" => ship.east(magnitude),
+            "S" => ship.south(magnitude),
+            "W" => ship.west(magnitude),
+            "L" => ship.left(magnitude),
+            "R" => ship.right(magnitude),
+            "F" => ship.forward(magnitude),
             _ => return Err(ProblemInvariantError.into()),
-        }
+        };
+        moved.ok_or(NavigationError::TooFar { line: i + 1 })?;
     }
 
-    println!("{}", ship.pos_x.abs() + ship.pos_y.abs());
+    Ok(ship.distance().ok_or(NavigationError::Distance)?)
+}
 
-    Ok(())
+/// The distance the ship ends up from where it started, moving its waypoint as instructed.
+fn navigate_by_waypoint(instructions: &[NavigationInstruction]) -> anyhow::Result<i32> {
+    let mut ship = Ship::default();
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        let magnitude = instruction.value;
+        let moved = match instruction.action {
+            "N" => ship.waypoint_north(magnitude),
+            "E" => ship.waypoint_east(magnitude),
+            "S" => ship.waypoint_south(magnitude),
+            "W" => ship.waypoint_west(magnitude),
+            "L" => ship.waypoint_left(magnitude),
+            "R" => ship.waypoint_right(magnitude),
+            "F" => ship.forward_towards_waypoint(magnitude),
+            _ => return Err(ProblemInvariantError.into()),
+        };
+        moved.ok_or(NavigationError::TooFar { line: i + 1 })?;
+    }
+
+    Ok(ship.distance().ok_or(NavigationError::Distance)?)
 }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day12.txt");
-    let display = path.display();
+/// `size` random navigation instructions.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=100_000)?;
+
+    let mut input = String::new();
+    for _ in 0..size {
+        let instruction = match rng.gen_range(0..10) {
+            0..=2 => format!("F{}", rng.gen_range(1..=100)),
+            3..=6 => format!(
+                "{}{}",
+                ['N', 'E', 'S', 'W'].choose(rng).unwrap(),
+                rng.gen_range(1..=100)
+            ),
+            _ => format!(
+                "{}{}",
+                ['L', 'R'].choose(rng).unwrap(),
+                [90, 180, 270].choose(rng).unwrap()
+            ),
+        };
+        input += &instruction;
+        input.push('\n');
+    }
+    Ok(input)
+}
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+/// Parses one navigation instruction per line. Distances and angles must fit in an `i32`.
+fn parse_navigation_instructions<S: AsRef<str>>(
+    lines: &[S],
+) -> Result<Vec<NavigationInstruction<'_>>, ParseNavigationError> {
+    let instruction = pair(
+        one_of(&["N", "E", "S", "W", "L", "R", "F"]),
+        unsigned::<u32>(),
+    );
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let (action, value) = parser::parse(&instruction, line.as_ref()).map_err(|source| {
+                ParseNavigationError::Syntax {
+                    line: i + 1,
+                    source,
+                }
+            })?;
+            if i32::try_from(value).is_err() {
+                return Err(ParseNavigationError::TooLarge { line: i + 1, value });
+            }
+            if (action == "L" || action == "R") && value % 90 != 0 {
+                return Err(ParseNavigationError::Turn {
+                    line: i + 1,
+                    degrees: value,
+                });
+            }
+            Ok(NavigationInstruction { action, value })
+        })
+        .collect()
+}
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+/// Checks that `lines` parse as navigation instructions.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    parse_navigation_instructions(lines)?;
+    Ok(())
+}
 
-    let mut ship = Ship::default();
+/// Solves both parts of the puzzle for the navigation instructions in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let instructions = parse_navigation_instructions(lines)?;
+    let first = navigate(&instructions);
+    let second = navigate_by_waypoint(&instructions);
+    first?;
+    second?;
+    Ok(())
+}
 
-    for line in &lines {
-        if line.len() < 2 {
-            return Err(ProblemInvariantError.into());
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let command = line.chars().next().unwrap();
-        let magnitude = line[1..].parse::<u32>()?;
-
-        match command {
-            'N' => ship.waypoint_north(magnitude),
-            'E' => ship.waypoint_east(magnitude),
-            'S' => ship.waypoint_south(magnitude),
-            'W' => ship.waypoint_west(magnitude),
-            'L' => ship.waypoint_left(magnitude),
-            'R' => ship.waypoint_right(magnitude),
-            'F' => ship.forward_towards_waypoint(magnitude),
-            _ => return Err(ProblemInvariantError.into()),
-        }
+    #[test]
+    fn test_navigate() {
+        let instructions =
+            parse_navigation_instructions(&["F10", "N3", "F7", "R90", "F11"]).unwrap();
+        assert_eq!(navigate(&instructions).unwrap(), 25);
+        assert_eq!(navigate_by_waypoint(&instructions).unwrap(), 286);
     }
 
-    println!("{}", ship.pos_x.abs() + ship.pos_y.abs());
+    #[test]
+    fn test_navigation_overflow() {
+        let instructions = parse_navigation_instructions(&["N2147483647", "N1"]).unwrap();
+        assert_eq!(
+            navigate(&instructions).unwrap_err().to_string(),
+            "line 2: the ship goes too far to keep track of"
+        );
+        assert!(navigate_by_waypoint(&instructions).is_err());
+
+        let instructions = parse_navigation_instructions(&["S2147483647", "W2"]).unwrap();
+        assert_eq!(
+            navigate(&instructions).unwrap_err().to_string(),
+            "the ship ends up too far away to measure the distance"
+        );
+
+        let instructions = parse_navigation_instructions(&["N1000", "F3000000"]).unwrap();
+        assert!(navigate(&instructions).is_ok());
+        assert!(navigate_by_waypoint(&instructions).is_err());
+    }
 
-    Ok(())
+    #[test]
+    fn test_parse_navigation_instructions() {
+        let instructions = parse_navigation_instructions(&["F10", "R90", "N2147483647"]).unwrap();
+        assert_eq!(
+            instructions,
+            vec![
+                NavigationInstruction {
+                    action: "F",
+                    value: 10
+                },
+                NavigationInstruction {
+                    action: "R",
+                    value: 90
+                },
+                NavigationInstruction {
+                    action: "N",
+                    value: 2147483647
+                },
+            ]
+        );
+
+        let too_large = parse_navigation_instructions(&["F10", "N2147483648"]).unwrap_err();
+        assert_eq!(
+            too_large.to_string(),
+            "line 2: distances and angles must be at most 2147483647, found 2147483648"
+        );
+        let too_many_digits = parse_navigation_instructions(&["F4294967296"]).unwrap_err();
+        assert!(matches!(
+            too_many_digits,
+            ParseNavigationError::Syntax { line: 1, .. }
+        ));
+        let turn = parse_navigation_instructions(&["L45"]).unwrap_err();
+        assert!(matches!(
+            turn,
+            ParseNavigationError::Turn {
+                line: 1,
+                degrees: 45
+            }
+        ));
+    }
 }
@@ -1,138 +1,372 @@
-use anyhow::Result;
-use regex::Regex;
-use std::convert::identity;
-use std::fs::File;
-use std::io::{self, prelude::*};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::path::Path;
 
-use lazy_static::lazy_static;
+use crate::generate::check_size;
+use crate::input;
+use crate::parser::{self, alt, literal, map, separated, unsigned, ParseError};
 
 #[derive(Debug)]
-struct ProblemInvariantError;
+enum ParseScheduleError {
+    MissingLine { line: usize },
+    Syntax { line: usize, source: ParseError },
+    InvalidId { id: u32 },
+    NoBusses,
+}
 
-impl std::error::Error for ProblemInvariantError {}
+impl std::error::Error for ParseScheduleError {}
 
-impl std::fmt::Display for ProblemInvariantError {
+impl std::fmt::Display for ParseScheduleError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "An invariant of the Advent of Code challenge seems to have been violated."
-        )
+        match self {
+            ParseScheduleError::MissingLine { line } => {
+                write!(
+                    f,
+                    "line {}: expected a line, found the end of the input",
+                    line
+                )
+            }
+            ParseScheduleError::Syntax { line, source } => write!(f, "line {}: {}", line, source),
+            ParseScheduleError::InvalidId { id } => {
+                write!(f, "line 2: bus IDs must be positive, found {}", id)
+            }
+            ParseScheduleError::NoBusses => write!(f, "line 2: there are no busses in service"),
+        }
     }
 }
 
-pub fn problem1() -> anyhow::Result<()> {
-    let path = Path::new("input_day13.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+#[derive(Debug, PartialEq)]
+enum TimestampError {
+    /// Two `(offset, id)` busses which never leave at their offsets from the same timestamp.
+    Incompatible {
+        first: (usize, u32),
+        second: (usize, u32),
+    },
+    Overflow,
+}
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+impl std::error::Error for TimestampError {}
 
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"(\d+|x),?").unwrap();
+impl std::fmt::Display for TimestampError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TimestampError::Incompatible { first, second } => write!(
+                f,
+                "busses {} and {} can't leave {} and {} minutes after the same timestamp",
+                first.1, second.1, first.0, second.0
+            ),
+            TimestampError::Overflow => write!(f, "the timestamp is too large to compute"),
+        }
     }
-    let start_time = lines[0].parse::<u32>()?;
-    let captures = RE.captures_iter(&lines[1]);
-    let all_busses = captures
-        .map(|capture| match capture.get(1).unwrap().as_str() {
-            "x" => None,
-            s => Some(s.parse::<u32>().unwrap()),
-        })
-        .collect::<Vec<_>>();
+}
 
-    let active_busses = all_busses.iter().filter_map(|x| *x).collect::<Vec<_>>();
+/// The earliest time to leave, and the bus in each slot of the schedule, if any.
+#[derive(Debug)]
+struct Schedule {
+    start_time: u32,
+    busses: Vec<Option<u32>>,
+}
 
-    let previous_departures = active_busses.iter().map(|t| ((start_time - 1) / t) * t);
-    let next_arrivals = previous_departures
-        .zip(&active_busses)
-        .map(|(t0, dt)| t0 + dt)
-        .collect::<Vec<_>>();
-    let (soonest_arrival_index, soonest_arrival_time) = next_arrivals
+pub fn problem1() -> anyhow::Result<u64> {
+    let schedule = read_schedule(Path::new("input_day13.txt"))?;
+    Ok(soonest_bus(&schedule))
+}
+
+pub fn problem2() -> anyhow::Result<u128> {
+    let schedule = read_schedule(Path::new("input_day13.txt"))?;
+    Ok(earliest_timestamp(&busses_and_offsets(&schedule))?)
+}
+
+/// The ID of the first bus to leave at or after the start time, times the wait for it.
+fn soonest_bus(schedule: &Schedule) -> u64 {
+    let start_time = u64::from(schedule.start_time);
+    let (soonest_arrival_time, soonest_arrival_id) = schedule
+        .busses
         .iter()
-        .enumerate()
-        .min_by_key(|&(_, x)| x)
+        .filter_map(|x| x.map(u64::from))
+        .map(|t| (start_time + (t - start_time % t) % t, t))
+        .min_by_key(|&(x, _)| x)
         .unwrap();
-    let soonest_arrival_id = active_busses[soonest_arrival_index];
-
-    println!(
-        "{}",
-        soonest_arrival_id * (soonest_arrival_time - start_time)
-    );
 
-    Ok(())
+    soonest_arrival_id * (soonest_arrival_time - start_time)
 }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day13.txt");
-    let display = path.display();
+/// The ID of each bus in service, with its slot in the schedule.
+fn busses_and_offsets(schedule: &Schedule) -> Vec<(usize, u32)> {
+    schedule
+        .busses
+        .iter()
+        .enumerate()
+        .filter_map(|(offset, bus)| bus.map(|id| (offset, id)))
+        .collect()
+}
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+/// Finds the earliest positive timestamp at which each bus `id` departs `offset` minutes
+/// after the timestamp.
+///
+/// Busses whose IDs share a factor `g` can only both leave on time if their offsets agree
+/// modulo `g`. There is a timestamp exactly when every pair of busses agrees like this, so
+/// the pairs are checked first.
+fn earliest_timestamp(busses_and_offsets: &[(usize, u32)]) -> Result<u128, TimestampError> {
+    for (i, &first) in busses_and_offsets.iter().enumerate() {
+        for &second in &busses_and_offsets[i + 1..] {
+            let g = gcd(first.1, second.1) as usize;
+            if first.0 % g != second.0 % g {
+                return Err(TimestampError::Incompatible { first, second });
+            }
+        }
+    }
 
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"(\d+|x),?").unwrap();
+    let mut affine = (0, 1);
+    for &(offset, id) in busses_and_offsets {
+        affine = add_bus(&affine, id as u128, offset as u128).ok_or(TimestampError::Overflow)?;
     }
-    let captures = RE.captures_iter(&lines[1]);
-    let all_busses_and_offsets = captures
-        .map(|capture| match capture.get(1).unwrap().as_str() {
-            "x" => None,
-            s => Some(s.parse::<u32>().unwrap()),
-        })
-        .enumerate()
-        .collect::<Vec<_>>();
+    Ok(affine.0)
+}
 
-    let active_busses_and_offsets = all_busses_and_offsets
-        .iter()
-        .map(|(offset, bus)| match bus {
-            None => None,
-            Some(id) => Some((offset, id)),
-        })
-        .filter_map(identity)
+/// A random bus schedule with `size` slots, up to ten of which are buses with distinct
+/// prime IDs, starting with the first slot.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=1000)?;
+
+    let primes = (11..1000u32)
+        .filter(|&n| (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0))
         .collect::<Vec<_>>();
+    let num_buses = size.min(10);
+    let mut ids = primes.choose_multiple(rng, num_buses);
+    let mut slots = vec!["x".to_string(); size];
+    slots[0] = ids.next().unwrap().to_string();
+    for (slot, id) in (1..size)
+        .collect::<Vec<_>>()
+        .choose_multiple(rng, num_buses - 1)
+        .zip(ids)
+    {
+        slots[*slot] = id.to_string();
+    }
 
-    let mut affine = (0, 1);
-    for (&offset, &id) in &active_busses_and_offsets {
-        affine = foo(&affine, id as u128, offset as u128);
+    Ok(format!(
+        "{}\n{}\n",
+        rng.gen_range(1000..=10_000_000),
+        slots.join(",")
+    ))
+}
+
+/// Parses the earliest time to leave on the first line, and the comma-separated bus IDs,
+/// or `x` for slots without a bus, on the second. Anything after that must be blank.
+fn parse_schedule<S: AsRef<str>>(lines: &[S]) -> Result<Schedule, ParseScheduleError> {
+    let line = |i: usize| {
+        lines
+            .get(i)
+            .map(AsRef::as_ref)
+            .ok_or(ParseScheduleError::MissingLine { line: i + 1 })
+    };
+    let syntax = |i: usize| {
+        move |source| ParseScheduleError::Syntax {
+            line: i + 1,
+            source,
+        }
+    };
+
+    let start_time = parser::parse(&unsigned(), line(0)?).map_err(syntax(0))?;
+    let slot = alt(map(literal("x"), |_| None), map(unsigned(), Some));
+    let busses = parser::parse(&separated(slot, literal(",")), line(1)?).map_err(syntax(1))?;
+    if let Some(&id) = busses.iter().flatten().find(|&&id| id == 0) {
+        return Err(ParseScheduleError::InvalidId { id });
+    }
+    if busses.iter().all(Option::is_none) {
+        return Err(ParseScheduleError::NoBusses);
+    }
+    if let Some(i) = (2..lines.len()).find(|&i| !lines[i].as_ref().trim().is_empty()) {
+        let source = ParseError {
+            position: 0,
+            expected: "end of input".to_string(),
+        };
+        return Err(ParseScheduleError::Syntax {
+            line: i + 1,
+            source,
+        });
     }
 
-    println!("{}", affine.0);
+    Ok(Schedule { start_time, busses })
+}
 
+fn read_schedule(path: &Path) -> anyhow::Result<Schedule> {
+    let lines = input::read_lines(path)?;
+    Ok(parse_schedule(&lines)?)
+}
+
+/// Checks that `lines` parse as a bus schedule.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    parse_schedule(lines)?;
     Ok(())
 }
 
-/// Given an affine function which produces integer multiples of some values and offsets,
-/// compute a new affine function which also includes a new divisor at some offset.
+/// Solves both parts of the puzzle for the bus schedule in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let schedule = parse_schedule(lines)?;
+    soonest_bus(&schedule);
+    earliest_timestamp(&busses_and_offsets(&schedule))?;
+    Ok(())
+}
+
+/// Given an affine function which produces the timestamps at which some busses leave on
+/// time, compute a new affine function which also has a bus with ID `divisor` leave
+/// `offset` minutes later.
+///
+/// `affine_function` - (a, b) representing y = a + b * x, where `a` is the first positive
+/// timestamp, or zero if there are no busses yet.
 ///
-/// `affine_function` - (a, b) representing y = a + b * x
-fn foo(affine_function: &(u128, u128), divisor: u128, offset: u128) -> (u128, u128) {
+/// Some `a + b * x + offset` must be a multiple of `divisor`, which holds when the busses
+/// have been checked pairwise. Returns `None` if the values overflow.
+fn add_bus(affine_function: &(u128, u128), divisor: u128, offset: u128) -> Option<(u128, u128)> {
     let (a, b) = *affine_function;
-    let mut i = a;
-    let mut result = (None, None);
-
-    loop {
-        if (i + offset) % divisor == 0 && (i + offset) != 0 {
-            match result.0 {
-                None => result.0 = Some(i),
-                Some(_) => {
-                    result.1 = Some(i - result.0.unwrap());
-                    break;
-                }
-            }
+    // Reducing `b * x` congruent to `remainder` modulo `divisor` by their common factor
+    // leaves a congruence modulo `period` whose solution repeats every `period` steps of `x`.
+    let common = u128::from(gcd((b % divisor) as u32, divisor as u32));
+    let period = divisor / common;
+    let remainder = (divisor - (a % divisor + offset % divisor) % divisor) % divisor;
+    let mut x = remainder / common * modular_inverse(b / common % period, period) % period;
+    if a == 0 && x == 0 {
+        x = period;
+    }
+    let step = b.checked_mul(period)?;
+
+    Some((a.checked_add(b.checked_mul(x)?)?, step))
+}
+
+/// The `x` with `value * x` congruent to 1 modulo `modulus`, where `value` and `modulus` are
+/// coprime and `modulus` fits in a `u32`.
+fn modular_inverse(value: u128, modulus: u128) -> u128 {
+    let (mut r0, mut r1) = (modulus as i128, value as i128);
+    let (mut t0, mut t1) = (0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+    t0.rem_euclid(modulus as i128) as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+
+    use super::*;
+    use crate::differential::{self, assert_agree};
+
+    /// Tries every positive timestamp up to the product of the IDs, after which the
+    /// departures repeat.
+    fn brute_force_earliest_timestamp(busses_and_offsets: &[(usize, u32)]) -> Option<u128> {
+        let product = busses_and_offsets
+            .iter()
+            .map(|&(_, id)| u128::from(id))
+            .product();
+        (1..=product).find(|&t: &u128| {
+            busses_and_offsets
+                .iter()
+                .all(|&(offset, id)| (t + offset as u128) % u128::from(id) == 0)
+        })
+    }
+
+    #[test]
+    fn test_earliest_timestamp_against_brute_force() {
+        // Small IDs, many of which share factors, so that some schedules have no timestamp.
+        const IDS: [u32; 14] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 13, 15, 18];
+
+        for case in 0..differential::CASES {
+            let mut rng = differential::rng(case);
+            let num_busses = rng.gen_range(1..=4);
+            let busses_and_offsets = (0..num_busses)
+                .map(|_| (rng.gen_range(0..30), *IDS.choose(&mut rng).unwrap()))
+                .collect::<Vec<_>>();
+
+            // With no busses every timestamp works, but the search starts from the
+            // first bus, so only compare schedules which have one.
+            assert_agree(
+                &busses_and_offsets,
+                |busses| {
+                    (!busses.is_empty())
+                        .then(|| brute_force_earliest_timestamp(busses))
+                        .flatten()
+                },
+                |busses| {
+                    (!busses.is_empty())
+                        .then(|| earliest_timestamp(busses).ok())
+                        .flatten()
+                },
+            );
         }
-        i += b;
     }
 
-    (result.0.unwrap(), result.1.unwrap())
+    #[test]
+    fn test_earliest_timestamp() {
+        assert_eq!(earliest_timestamp(&[(0, 17), (2, 13), (3, 19)]), Ok(3417));
+        assert_eq!(
+            earliest_timestamp(&[(0, 1789), (1, 37), (2, 47), (3, 1889)]),
+            Ok(1202161486)
+        );
+
+        // Large IDs take as long as small ones.
+        let busses = [(0, 4294967291), (1, 4294967279), (5, 4294967231)];
+        let timestamp = earliest_timestamp(&busses).unwrap();
+        assert!(timestamp > 0);
+        for &(offset, id) in &busses {
+            assert_eq!((timestamp + offset as u128) % u128::from(id), 0);
+        }
+    }
+
+    #[test]
+    fn test_soonest_bus() {
+        let lines = ["939", "7,13,x,x,59,x,31,19"];
+        assert_eq!(soonest_bus(&parse_schedule(&lines).unwrap()), 295);
+        // Waiting for the bus mustn't overflow a `u32`.
+        let lines = ["4294967295", "4294967291"];
+        let schedule = parse_schedule(&lines).unwrap();
+        assert_eq!(soonest_bus(&schedule), 4294967291 * 4294967287);
+    }
+
+    #[test]
+    fn test_ids_which_are_not_coprime() {
+        assert_eq!(earliest_timestamp(&[(0, 4), (2, 6)]), Ok(4));
+        assert_eq!(earliest_timestamp(&[(0, 4), (6, 6)]), Ok(12));
+        assert_eq!(earliest_timestamp(&[(0, 7), (7, 7)]), Ok(7));
+        assert_eq!(earliest_timestamp(&[(0, 1), (3, 1)]), Ok(1));
+
+        // No timestamp is even and odd at once.
+        assert_eq!(
+            earliest_timestamp(&[(0, 4), (1, 6)]),
+            Err(TimestampError::Incompatible {
+                first: (0, 4),
+                second: (1, 6)
+            })
+        );
+        assert_eq!(
+            earliest_timestamp(&[(0, 7), (1, 3), (5, 7)])
+                .unwrap_err()
+                .to_string(),
+            "busses 7 and 7 can't leave 0 and 5 minutes after the same timestamp"
+        );
+    }
+
+    #[test]
+    fn test_timestamp_overflow() {
+        // The product of fourteen primes above 1000 doesn't fit in a u128.
+        let busses = (1000..)
+            .filter(|&n: &u32| (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0))
+            .take(14)
+            .map(|id| (1, id))
+            .collect::<Vec<_>>();
+        assert_eq!(earliest_timestamp(&busses), Err(TimestampError::Overflow));
+        assert!(earliest_timestamp(&busses[..12]).is_ok());
+    }
 }
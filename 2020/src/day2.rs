@@ -1,95 +1,295 @@
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::convert::TryFrom;
-use std::fs::File;
-use std::io::{self, prelude::*};
-use std::num::ParseIntError;
+use rand::rngs::StdRng;
+use rand::Rng;
 use std::path::Path;
 
-pub fn problem1() -> Result<(), ParseIntError> {
-    let path = Path::new("input_day2.txt");
-    let display = path.display();
+use crate::generate::check_size;
+use crate::input::read_lines;
+use crate::parser::{self, letter, literal, map, pair, terminated, unsigned, word, ParseError};
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+#[derive(Debug)]
+struct ParsePasswordEntryError {
+    line: String,
+    source: ParseError,
+}
 
-    let lines = io::BufReader::new(file).lines();
-    let count = lines
-        .map(|line| line.unwrap())
-        .filter(|line| problem1_validate_line(&line))
-        .count();
+impl std::error::Error for ParsePasswordEntryError {}
 
-    println!("{}", count);
-    Ok(())
+impl std::fmt::Display for ParsePasswordEntryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to parse password entry `{}`: {}.",
+            self.line, self.source
+        )
+    }
+}
+
+/// A line of the password database: `lo-hi c: password`.
+#[derive(Debug)]
+struct PasswordEntry<'a> {
+    lo: usize,
+    hi: usize,
+    letter: char,
+    password: &'a str,
+}
+
+/// A rule which a password entry either satisfies or violates for some reason.
+trait PasswordPolicy {
+    fn name(&self) -> &'static str;
+
+    /// Returns a description of why the entry violates the policy, if it does.
+    fn violation(&self, entry: &PasswordEntry) -> Option<String>;
+
+    fn is_valid(&self, entry: &PasswordEntry) -> bool {
+        self.violation(entry).is_none()
+    }
 }
 
-fn problem1_validate_line(s: &str) -> bool {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"^(\d+)-(\d+) ([a-z]): ([a-z]+)$").unwrap();
+/// The letter must appear between `lo` and `hi` times, inclusive.
+struct CountRangePolicy;
+
+impl PasswordPolicy for CountRangePolicy {
+    fn name(&self) -> &'static str {
+        "count range"
     }
-    let _groups = RE.captures(s);
-
-    match _groups {
-        None => panic!("Regex didn't match."),
-        Some(groups) => {
-            let lower = groups.get(1).unwrap().as_str().parse::<u32>().unwrap();
-            let upper = groups.get(2).unwrap().as_str().parse::<u32>().unwrap();
-            let character = groups.get(3).unwrap().as_str().chars().next().unwrap();
-            let password = groups.get(4).unwrap().as_str();
-            let count = u32::try_from(password.matches(character).count()).unwrap();
-
-            lower <= count && count <= upper
+
+    fn violation(&self, entry: &PasswordEntry) -> Option<String> {
+        let count = entry.password.matches(entry.letter).count();
+        if (entry.lo..=entry.hi).contains(&count) {
+            None
+        } else {
+            Some(format!(
+                "`{}` appears {} times, expected {} to {}",
+                entry.letter, count, entry.lo, entry.hi
+            ))
         }
     }
 }
 
-pub fn problem2() -> Result<(), ParseIntError> {
-    let path = Path::new("input_day2.txt");
-    let display = path.display();
+/// The letter must appear at exactly one of the 1-based positions `lo` and `hi`. A position
+/// outside of the password never contains the letter.
+struct PositionalXorPolicy;
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+impl PasswordPolicy for PositionalXorPolicy {
+    fn name(&self) -> &'static str {
+        "positional"
+    }
 
-    let lines = io::BufReader::new(file).lines();
-    let count = lines
-        .map(|line| line.unwrap())
-        .filter(|line| problem2_validate_line(&line))
-        .count();
+    fn violation(&self, entry: &PasswordEntry) -> Option<String> {
+        let letter_at = |position: usize| {
+            position
+                .checked_sub(1)
+                .and_then(|i| entry.password.chars().nth(i))
+                == Some(entry.letter)
+        };
+
+        match (letter_at(entry.lo), letter_at(entry.hi)) {
+            (true, false) | (false, true) => None,
+            (true, true) => Some(format!(
+                "`{}` is at both positions {} and {}",
+                entry.letter, entry.lo, entry.hi
+            )),
+            (false, false) => {
+                let length = entry.password.chars().count();
+                let out_of_range = [entry.lo, entry.hi]
+                    .iter()
+                    .filter(|&&position| position == 0 || position > length)
+                    .map(|position| position.to_string())
+                    .collect::<Vec<_>>();
+                let mut reason = format!(
+                    "`{}` is at neither position {} nor {}",
+                    entry.letter, entry.lo, entry.hi
+                );
+                if !out_of_range.is_empty() {
+                    reason += &format!(
+                        " (out of range for a password of length {}: {})",
+                        length,
+                        out_of_range.join(", ")
+                    );
+                }
+                Some(reason)
+            }
+        }
+    }
+}
+
+pub fn problem1() -> anyhow::Result<usize> {
+    let lines = read_lines(Path::new("input_day2.txt"))?;
+    count_valid(&lines, &CountRangePolicy)
+}
+
+pub fn problem2() -> anyhow::Result<usize> {
+    let lines = read_lines(Path::new("input_day2.txt"))?;
+    count_valid(&lines, &PositionalXorPolicy)
+}
+
+/// Prints every line of the database at `path` which cannot be parsed or violates a policy,
+/// along with the reason.
+pub fn report(path: &Path) -> anyhow::Result<()> {
+    let policies: [&dyn PasswordPolicy; 2] = [&CountRangePolicy, &PositionalXorPolicy];
+
+    let lines = read_lines(path)?;
+    for (i, line) in lines.iter().enumerate() {
+        let entry = match parse_password_entry(line) {
+            Ok(entry) => entry,
+            Err(e) => {
+                println!("line {}: {}", i + 1, e);
+                continue;
+            }
+        };
+
+        for policy in &policies {
+            if let Some(reason) = policy.violation(&entry) {
+                println!("line {}: {} policy: {}", i + 1, policy.name(), reason);
+            }
+        }
+    }
 
-    println!("{}", count);
     Ok(())
 }
 
-fn problem2_validate_line(s: &str) -> bool {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"^(\d+)-(\d+) ([a-z]): ([a-z]+)$").unwrap();
+/// A random password database of `size` entries.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=100_000)?;
+
+    let mut input = String::new();
+    for _ in 0..size {
+        let lo = rng.gen_range(1..=10);
+        let hi = rng.gen_range(lo..=20);
+        let letter = rng.gen_range('a'..='z');
+        let length = rng.gen_range(1..=hi + 2);
+        let password = (0..length)
+            .map(|_| {
+                if rng.gen_bool(0.3) {
+                    letter
+                } else {
+                    rng.gen_range('a'..='z')
+                }
+            })
+            .collect::<String>();
+        input += &format!("{}-{} {}: {}\n", lo, hi, letter, password);
     }
-    let _groups = RE.captures(s);
-
-    match _groups {
-        None => panic!("Regex didn't match."),
-        Some(groups) => {
-            let first = groups.get(1).unwrap().as_str().parse::<u32>().unwrap();
-            let second = groups.get(2).unwrap().as_str().parse::<u32>().unwrap();
-            let character = groups.get(3).unwrap().as_str().chars().next().unwrap();
-            let password = groups.get(4).unwrap().as_str();
-
-            let first_match = password
-                .chars()
-                .nth(usize::try_from(first - 1).unwrap())
-                .unwrap()
-                == character;
-            let second_match = password
-                .chars()
-                .nth(usize::try_from(second - 1).unwrap())
-                .unwrap()
-                == character;
-
-            first_match ^ second_match
+    Ok(input)
+}
+
+fn count_valid<S: AsRef<str>>(lines: &[S], policy: &dyn PasswordPolicy) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for line in lines {
+        if policy.is_valid(&parse_password_entry(line.as_ref())?) {
+            count += 1;
         }
     }
+    Ok(count)
+}
+
+fn parse_password_entry(line: &str) -> Result<PasswordEntry<'_>, ParsePasswordEntryError> {
+    let entry = map(
+        pair(
+            pair(
+                terminated(unsigned(), literal("-")),
+                terminated(unsigned(), literal(" ")),
+            ),
+            pair(terminated(letter(), literal(": ")), word()),
+        ),
+        |((lo, hi), (letter, password))| PasswordEntry {
+            lo,
+            hi,
+            letter,
+            password,
+        },
+    );
+    parser::parse(&entry, line).map_err(|source| ParsePasswordEntryError {
+        line: line.to_string(),
+        source,
+    })
+}
+
+/// Checks that `lines` parse as a password database.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    for line in lines {
+        parse_password_entry(line)?;
+    }
+    Ok(())
+}
+
+/// Solves both parts of the puzzle for the password database in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    count_valid(lines, &CountRangePolicy)?;
+    count_valid(lines, &PositionalXorPolicy)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 3] = ["1-3 a: abcde", "1-3 b: cdefg", "2-9 c: ccccccccc"];
+
+    fn violations(policy: &dyn PasswordPolicy) -> Vec<Option<String>> {
+        EXAMPLE
+            .iter()
+            .map(|line| policy.violation(&parse_password_entry(line).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_count_range_policy() {
+        assert_eq!(
+            violations(&CountRangePolicy),
+            [
+                None,
+                Some("`b` appears 0 times, expected 1 to 3".to_string()),
+                None
+            ]
+        );
+
+        let lines = EXAMPLE
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(count_valid(&lines, &CountRangePolicy).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_positional_xor_policy() {
+        assert_eq!(
+            violations(&PositionalXorPolicy),
+            [
+                None,
+                Some("`b` is at neither position 1 nor 3".to_string()),
+                Some("`c` is at both positions 2 and 9".to_string())
+            ]
+        );
+
+        let lines = EXAMPLE
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(count_valid(&lines, &PositionalXorPolicy).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_positional_xor_policy_out_of_range() {
+        let entry = parse_password_entry("0-5 a: ba").unwrap();
+        assert_eq!(
+            PositionalXorPolicy.violation(&entry),
+            Some(
+                "`a` is at neither position 0 nor 5 \
+                 (out of range for a password of length 2: 0, 5)"
+                    .to_string()
+            )
+        );
+
+        // A position past the end never holds the letter, so the other one decides.
+        let entry = parse_password_entry("2-5 a: ba").unwrap();
+        assert!(PositionalXorPolicy.is_valid(&entry));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse_password_entry("1-3 a abcde").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Failed to parse password entry `1-3 a abcde`: "));
+    }
 }
@@ -1,65 +1,375 @@
 use anyhow::Result;
-use std::fs::File;
-use std::io::{self, prelude::*};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::num::{IntErrorKind, ParseIntError};
 use std::path::Path;
 
-pub fn problem1() -> anyhow::Result<()> {
-    let num_trees = count_trees(3, 1)?;
-    println!("{}", num_trees);
-    Ok(())
+use crate::generate::check_size;
+use crate::input;
+
+#[derive(Debug)]
+struct ParseMapError;
+
+impl std::error::Error for ParseMapError {}
+
+impl std::fmt::Display for ParseMapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Failed to parse map.")
+    }
 }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let product = count_trees(1, 1)?
-        * count_trees(3, 1)?
-        * count_trees(5, 1)?
-        * count_trees(7, 1)?
-        * count_trees(1, 2)?;
-    println!("{}", product);
-    Ok(())
+#[derive(Debug)]
+pub enum ParseSlopeError {
+    Syntax,
+    TooLarge,
+    NotDownhill,
 }
 
-fn count_trees(x_slope: usize, y_slope: usize) -> anyhow::Result<u32> {
-    let path = Path::new("input_day3.txt");
-    let display = path.display();
+impl std::error::Error for ParseSlopeError {}
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+impl std::fmt::Display for ParseSlopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseSlopeError::Syntax => write!(f, "Failed to parse slope."),
+            ParseSlopeError::TooLarge => write!(f, "Slopes must be at most {0}/{0}.", usize::MAX),
+            ParseSlopeError::NotDownhill => write!(f, "Slopes must go at least one square down."),
+        }
+    }
+}
 
-    let grid = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .map(|s| s.as_str())
-        .map(parse_line)
-        .collect::<Vec<Vec<bool>>>();
-
-    let height: usize = grid.len();
-    let width: usize = grid[0].len();
-    let mut num_trees: u32 = 0;
-    let mut x: usize = 0;
-    let mut y: usize = 0;
-
-    while y < height {
-        if grid[y][x % width] {
-            num_trees += 1;
+#[derive(Debug)]
+enum SurveyError {
+    PathOverflow { slope: Slope },
+    ProductOverflow,
+}
+
+impl std::error::Error for SurveyError {}
+
+impl std::fmt::Display for SurveyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SurveyError::PathOverflow { slope } => {
+                write!(f, "The path down slope {} goes too far right.", slope)
+            }
+            SurveyError::ProductOverflow => {
+                write!(f, "The product of the numbers of trees is too large.")
+            }
         }
+    }
+}
+
+/// A step of `right` squares to the right and `down` squares down, taken as given.
+///
+/// Slopes are not reduced: `2/2` only visits every other row, so it hits different trees
+/// from `1/1`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slope {
+    right: usize,
+    down: usize,
+}
+
+impl Slope {
+    fn new(right: usize, down: usize) -> Option<Slope> {
+        if down == 0 {
+            return None;
+        }
+        Some(Slope { right, down })
+    }
+}
+
+impl std::str::FromStr for Slope {
+    type Err = ParseSlopeError;
 
-        x += x_slope;
-        y += y_slope;
+    /// Parses `right/down`, or just `right` for a slope of one square down.
+    fn from_str(s: &str) -> Result<Slope, ParseSlopeError> {
+        let (right, down) = match s.split_once('/') {
+            Some((right, down)) => (right, down),
+            None => (s, "1"),
+        };
+        let parse = |s: &str| {
+            s.parse().map_err(|e: ParseIntError| match e.kind() {
+                IntErrorKind::PosOverflow => ParseSlopeError::TooLarge,
+                _ => ParseSlopeError::Syntax,
+            })
+        };
+        Slope::new(parse(right)?, parse(down)?).ok_or(ParseSlopeError::NotDownhill)
     }
+}
+
+impl std::fmt::Display for Slope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", self.right, self.down)
+    }
+}
 
-    Ok(num_trees)
+/// The number of trees hit on each of a set of slopes.
+#[derive(Debug)]
+struct Survey {
+    trees_per_slope: Vec<(Slope, usize)>,
 }
 
-fn parse_line(s: &str) -> Vec<bool> {
-    s.chars()
-        .map(|c| match c {
-            '.' => false,
-            '#' => true,
-            _ => panic!("Unexpected character"),
+impl Survey {
+    fn product(&self) -> Result<usize, SurveyError> {
+        self.trees_per_slope
+            .iter()
+            .try_fold(1usize, |product, &(_, trees)| product.checked_mul(trees))
+            .ok_or(SurveyError::ProductOverflow)
+    }
+}
+
+/// The area below the toboggan, which repeats indefinitely to the right.
+#[derive(Debug)]
+struct TreeMap {
+    trees: Vec<Vec<bool>>,
+    width: usize,
+}
+
+impl TreeMap {
+    fn new(lines: &[&str]) -> Result<TreeMap, ParseMapError> {
+        let trees = lines
+            .iter()
+            .map(|line| {
+                line.chars()
+                    .map(|c| match c {
+                        '.' => Ok(false),
+                        '#' => Ok(true),
+                        _ => Err(ParseMapError),
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = trees.first().map_or(0, |row| row.len());
+        if width == 0 || trees.iter().any(|row| row.len() != width) {
+            return Err(ParseMapError);
+        }
+
+        Ok(TreeMap { trees, width })
+    }
+
+    /// The squares visited when starting from the top left corner, as `(x, y)` pairs with
+    /// `x` not yet wrapped around the width of the map.
+    fn path(&self, slope: Slope) -> Result<Vec<(usize, usize)>, SurveyError> {
+        (0..self.trees.len())
+            .step_by(slope.down)
+            .enumerate()
+            .map(|(i, y)| {
+                let x = i
+                    .checked_mul(slope.right)
+                    .ok_or(SurveyError::PathOverflow { slope })?;
+                Ok((x, y))
+            })
+            .collect()
+    }
+
+    fn count_trees(&self, slope: Slope) -> Result<usize, SurveyError> {
+        Ok(self
+            .path(slope)?
+            .into_iter()
+            .filter(|&(x, y)| self.trees[y][x % self.width])
+            .count())
+    }
+
+    fn survey(&self, slopes: &[Slope]) -> Result<Survey, SurveyError> {
+        Ok(Survey {
+            trees_per_slope: slopes
+                .iter()
+                .map(|&slope| Ok((slope, self.count_trees(slope)?)))
+                .collect::<Result<_, SurveyError>>()?,
         })
-        .collect::<Vec<bool>>()
+    }
+
+    /// Draws the map, repeated as far right as the path reaches, with visited open squares
+    /// marked `O` and trees hit marked `X`.
+    fn render(&self, slope: Slope) -> Result<String, SurveyError> {
+        let mut visited_x = vec![None; self.trees.len()];
+        for (x, y) in self.path(slope)? {
+            visited_x[y] = Some(x);
+        }
+        let max_x = visited_x.iter().flatten().copied().max().unwrap_or(0);
+        let width = (max_x / self.width + 1)
+            .checked_mul(self.width)
+            .ok_or(SurveyError::PathOverflow { slope })?;
+
+        let mut output = String::new();
+        for (y, row) in self.trees.iter().enumerate() {
+            for x in 0..width {
+                let tree = row[x % self.width];
+                output.push(match (visited_x[y] == Some(x), tree) {
+                    (true, true) => 'X',
+                    (true, false) => 'O',
+                    (false, true) => '#',
+                    (false, false) => '.',
+                });
+            }
+            output.push('\n');
+        }
+        Ok(output)
+    }
+}
+
+pub fn problem1() -> anyhow::Result<usize> {
+    let map = read_map(Path::new("input_day3.txt"))?;
+    Ok(part1(&map)?)
+}
+
+pub fn problem2() -> anyhow::Result<usize> {
+    let map = read_map(Path::new("input_day3.txt"))?;
+    Ok(part2(&map)?)
+}
+
+fn part1(map: &TreeMap) -> Result<usize, SurveyError> {
+    map.count_trees(Slope::new(3, 1).unwrap())
+}
+
+fn part2(map: &TreeMap) -> Result<usize, SurveyError> {
+    let slopes = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)]
+        .iter()
+        .map(|&(right, down)| Slope::new(right, down).unwrap())
+        .collect::<Vec<_>>();
+    map.survey(&slopes)?.product()
+}
+
+/// Prints the number of trees hit on each slope across the map at `path`, followed by
+/// their product.
+pub fn survey(path: &Path, slopes: &[Slope]) -> anyhow::Result<()> {
+    let map = read_map(path)?;
+    let survey = map.survey(slopes)?;
+    for (slope, trees) in &survey.trees_per_slope {
+        println!("{}: {}", slope, trees);
+    }
+    println!("product: {}", survey.product()?);
+    Ok(())
+}
+
+/// Prints the map at `path` with the path taken down `slope` drawn over it.
+pub fn render(path: &Path, slope: Slope) -> anyhow::Result<()> {
+    let map = read_map(path)?;
+    print!("{}", map.render(slope)?);
+    Ok(())
+}
+
+/// A random map `size` squares tall and as wide as the puzzle's.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=100_000)?;
+
+    let mut input = String::new();
+    for _ in 0..size {
+        input.extend((0..31).map(|_| if rng.gen_bool(0.2) { '#' } else { '.' }));
+        input.push('\n');
+    }
+    Ok(input)
+}
+
+fn read_map(path: &Path) -> anyhow::Result<TreeMap> {
+    let lines = input::read_lines(path)?;
+    let map = TreeMap::new(&lines.iter().map(AsRef::as_ref).collect::<Vec<_>>())?;
+    Ok(map)
+}
+
+/// Checks that `lines` parse as a map.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    TreeMap::new(lines)?;
+    Ok(())
+}
+
+/// Solves both parts of the puzzle for the map in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let map = TreeMap::new(lines)?;
+    part1(&map)?;
+    part2(&map)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 11] = [
+        "..##.......",
+        "#...#...#..",
+        ".#....#..#.",
+        "..#.#...#.#",
+        ".#...##..#.",
+        "..#.##.....",
+        ".#.#.#....#",
+        ".#........#",
+        "#.##...#...",
+        "#...##....#",
+        ".#..#...#.#",
+    ];
+
+    #[test]
+    fn test_example() {
+        let map = TreeMap::new(&EXAMPLE).unwrap();
+        let slopes = ["1", "3", "5", "7", "1/2"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect::<Vec<Slope>>();
+        let survey = map.survey(&slopes).unwrap();
+        let trees = survey
+            .trees_per_slope
+            .iter()
+            .map(|&(_, trees)| trees)
+            .collect::<Vec<_>>();
+        assert_eq!(trees, [2, 7, 3, 4, 2]);
+        assert_eq!(survey.product().unwrap(), 336);
+    }
+
+    #[test]
+    fn test_slope_is_not_reduced() {
+        let slope = "2/2".parse::<Slope>().unwrap();
+        assert_eq!(slope.to_string(), "2/2");
+
+        let map = TreeMap::new(&EXAMPLE).unwrap();
+        assert_eq!(
+            map.path(slope).unwrap(),
+            [(0, 0), (2, 2), (4, 4), (6, 6), (8, 8), (10, 10)]
+        );
+        assert_eq!(map.count_trees(slope).unwrap(), 1);
+        assert_eq!(map.count_trees("1/1".parse().unwrap()).unwrap(), 2);
+
+        assert!(matches!(
+            "1/0".parse::<Slope>(),
+            Err(ParseSlopeError::NotDownhill)
+        ));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let too_large = format!("{}0", usize::MAX);
+        assert!(matches!(
+            too_large.parse::<Slope>(),
+            Err(ParseSlopeError::TooLarge)
+        ));
+        assert!(matches!(
+            format!("1/{}", too_large).parse::<Slope>(),
+            Err(ParseSlopeError::TooLarge)
+        ));
+        assert!(matches!(
+            "1/x".parse::<Slope>(),
+            Err(ParseSlopeError::Syntax)
+        ));
+
+        let map = TreeMap::new(&EXAMPLE).unwrap();
+        let steep = usize::MAX.to_string().parse().unwrap();
+        assert!(matches!(
+            map.count_trees(steep),
+            Err(SurveyError::PathOverflow { .. })
+        ));
+        assert!(map.render(steep).is_err());
+
+        // Every square is a tree, so each slope hits one per row.
+        let rows = ["#"; 256];
+        let map = TreeMap::new(&rows).unwrap();
+        let slopes = vec!["1".parse().unwrap(); usize::BITS as usize / 8];
+        assert_eq!(
+            map.survey(&slopes[1..]).unwrap().product().unwrap(),
+            1 << (usize::BITS - 8)
+        );
+        assert!(matches!(
+            map.survey(&slopes).unwrap().product(),
+            Err(SurveyError::ProductOverflow)
+        ));
+    }
 }
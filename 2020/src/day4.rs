@@ -1,165 +1,197 @@
-use anyhow::Result;
-use lazy_static::lazy_static;
-use regex::Regex;
+use anyhow::{Context, Result};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, prelude::*};
 use std::path::Path;
 
-#[allow(dead_code)]
-struct Passport {
-    byr: String,
-    iyr: String,
-    eyr: String,
-    hgt: String,
-    hcl: String,
-    ecl: String,
-    pid: String,
-    cid: Option<String>,
-}
-
-pub fn problem1() -> anyhow::Result<()> {
-    let path = Path::new("input_day4.txt");
-    let display = path.display();
+use crate::generate::check_size;
+use crate::input::{self, read_lines};
+use crate::records::{self, KeyValueError};
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+mod passport;
+mod schema;
 
-    let passports = parse_passports(&file)?;
-    println!("{}", passports.len());
-    Ok(())
-}
+use passport::{Passport, PassportError};
+use schema::Schema;
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day4.txt");
-    let display = path.display();
+/// The puzzle's rules, built in so that solving doesn't depend on the working directory.
+const DEFAULT_SCHEMA: &str = include_str!("../passport_schema.txt");
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
-
-    let num_passports = parse_passports(&file)?
-        .into_iter()
-        .filter(validate_passport)
-        .count();
-    println!("{}", num_passports);
-    Ok(())
+/// The raw `key:value` fields of a passport, along with the line it starts on, before they
+/// are checked against the schema and converted to a typed `Passport`.
+#[derive(Debug)]
+struct PassportRecord<'a> {
+    line: usize,
+    fields: HashMap<&'a str, &'a str>,
 }
 
-fn parse_passports(file: &File) -> anyhow::Result<Vec<Passport>> {
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
-
-    let groups = lines.split(|line| line.is_empty());
-    let passports = groups
-        .map(|group| parse_group(&group.to_vec()))
-        .filter_map(|passport| passport)
-        .collect::<Vec<Passport>>();
-    Ok(passports)
+pub fn problem1() -> anyhow::Result<usize> {
+    let schema = read_schema(None)?;
+    let lines = read_lines(Path::new("input_day4.txt"))?;
+    Ok(count_complete(&schema, &parse_passports(&lines)?))
 }
 
-fn parse_group(group: &[String]) -> Option<Passport> {
-    let kvps = group
-        .iter()
-        .map(|line| line.split(' ').collect::<Vec<_>>())
-        .collect::<Vec<_>>()
-        .concat();
+pub fn problem2() -> anyhow::Result<usize> {
+    let schema = read_schema(None)?;
+    let lines = read_lines(Path::new("input_day4.txt"))?;
+    count_valid(&schema, &parse_passports(&lines)?)
+}
 
-    let fields = kvps
+/// The number of passports with every field the schema requires.
+fn count_complete(schema: &Schema, records: &[PassportRecord]) -> usize {
+    records
         .iter()
-        .map(|kvp| kvp.split(':'))
-        .map(|mut arr| (arr.next().zip(arr.next())))
-        .collect::<Option<Vec<_>>>()?
-        .into_iter()
-        .collect::<HashMap<_, _>>();
-
-    let passport = Passport {
-        byr: (*fields.get("byr")?).to_string(),
-        iyr: (*fields.get("iyr")?).to_string(),
-        eyr: (*fields.get("eyr")?).to_string(),
-        hgt: (*fields.get("hgt")?).to_string(),
-        hcl: (*fields.get("hcl")?).to_string(),
-        ecl: (*fields.get("ecl")?).to_string(),
-        pid: (*fields.get("pid")?).to_string(),
-        cid: fields.get("cid").map(|x| x.to_string()),
-    };
-    Some(passport)
+        .filter(|record| {
+            schema
+                .validate(&record.fields)
+                .iter()
+                .all(|error| !error.is_missing())
+        })
+        .count()
 }
 
-fn validate_passport(passport: &Passport) -> bool {
-    validate_byr(passport)
-        && validate_iyr(passport)
-        && validate_eyr(passport)
-        && validate_hgt(passport)
-        && validate_hcl(passport)
-        && validate_ecl(passport)
-        && validate_pid(passport)
+/// The number of passports which convert to a typed `Passport`.
+fn count_valid(schema: &Schema, records: &[PassportRecord]) -> anyhow::Result<usize> {
+    let mut num_passports = 0;
+    for record in records {
+        match Passport::new(schema, &record.fields) {
+            Ok(_) => num_passports += 1,
+            Err(PassportError::Rejected(_)) => {}
+            Err(e) => return Err(e).with_context(|| format!("passport on line {}", record.line)),
+        }
+    }
+    Ok(num_passports)
 }
 
-fn validate_byr(passport: &Passport) -> bool {
-    match passport.byr.parse::<u32>() {
-        Err(_) => false,
-        Ok(x) => (1920..=2002).contains(&x),
+/// Prints every problem with every passport at `path`, validated against the schema at
+/// `schema_path`, or the puzzle's rules if there isn't one.
+pub fn report(path: &Path, schema_path: Option<&Path>) -> anyhow::Result<()> {
+    let schema = read_schema(schema_path)?;
+    let lines = read_lines(path)?;
+    for (i, record) in parse_passports(&lines)?.iter().enumerate() {
+        match Passport::new(&schema, &record.fields) {
+            Ok(passport) => println!(
+                "passport {} (line {}): valid: {}",
+                i + 1,
+                record.line,
+                passport
+            ),
+            Err(PassportError::Rejected(_)) => {
+                println!("passport {} (line {}): invalid", i + 1, record.line)
+            }
+            Err(e) => println!(
+                "passport {} (line {}): malformed: {}",
+                i + 1,
+                record.line,
+                e
+            ),
+        }
+        for error in schema.validate(&record.fields) {
+            println!("    {}", error);
+        }
     }
+    Ok(())
 }
 
-fn validate_iyr(passport: &Passport) -> bool {
-    match passport.iyr.parse::<u32>() {
-        Err(_) => false,
-        Ok(x) => (2010..=2020).contains(&x),
+/// A random batch of `size` passports, some of which have missing or invalid fields.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    const REQUIRED_FIELDS: [&str; 7] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
+    const EYE_COLORS: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+    check_size(size, 1..=100_000)?;
+
+    let mut passports = Vec::with_capacity(size);
+    for _ in 0..size {
+        let mut fields = REQUIRED_FIELDS.to_vec();
+        if rng.gen_bool(0.5) {
+            fields.push("cid");
+        }
+        if rng.gen_bool(0.2) {
+            fields.remove(rng.gen_range(0..REQUIRED_FIELDS.len()));
+        }
+        let invalid = if rng.gen_bool(0.2) {
+            fields.choose(rng).copied()
+        } else {
+            None
+        };
+        fields.shuffle(rng);
+
+        let mut passport = String::new();
+        for (i, &field) in fields.iter().enumerate() {
+            if i > 0 {
+                passport.push(if rng.gen_bool(0.3) { '\n' } else { ' ' });
+            }
+            let valid = Some(field) != invalid;
+            let value = match (field, valid) {
+                ("byr", true) => rng.gen_range(1920..=2002).to_string(),
+                ("byr", false) => rng.gen_range(1900..1920).to_string(),
+                ("iyr", true) => rng.gen_range(2010..=2020).to_string(),
+                ("iyr", false) => rng.gen_range(2021..=2030).to_string(),
+                ("eyr", true) => rng.gen_range(2020..=2030).to_string(),
+                ("eyr", false) => rng.gen_range(2000..2020).to_string(),
+                ("hgt", true) if rng.gen_bool(0.5) => format!("{}cm", rng.gen_range(150..=193)),
+                ("hgt", true) => format!("{}in", rng.gen_range(59..=76)),
+                ("hgt", false) => rng.gen_range(150..=193).to_string(),
+                ("hcl", true) => format!("#{:06x}", rng.gen_range(0..0x100_0000)),
+                ("hcl", false) => format!("{:06x}", rng.gen_range(0..0x100_0000)),
+                ("ecl", true) => EYE_COLORS.choose(rng).unwrap().to_string(),
+                ("ecl", false) => "xry".to_string(),
+                ("pid", true) => format!("{:09}", rng.gen_range(0..1_000_000_000)),
+                ("pid", false) => format!("{:08}", rng.gen_range(0..100_000_000)),
+                _ => rng.gen_range(100..1000).to_string(),
+            };
+            passport += &format!("{}:{}", field, value);
+        }
+        passports.push(passport);
     }
+    Ok(passports.join("\n\n") + "\n")
 }
 
-fn validate_eyr(passport: &Passport) -> bool {
-    match passport.eyr.parse::<u32>() {
-        Err(_) => false,
-        Ok(x) => (2020..=2030).contains(&x),
-    }
+fn parse_passports<S: AsRef<str>>(lines: &[S]) -> anyhow::Result<Vec<PassportRecord<'_>>> {
+    let passports = records::paragraphs(lines)
+        .map(|(line, group)| {
+            Ok(PassportRecord {
+                line,
+                fields: records::parse_key_values(line, group)?
+                    .into_iter()
+                    .collect(),
+            })
+        })
+        .collect::<Result<Vec<_>, KeyValueError>>()?;
+    Ok(passports)
 }
 
-fn validate_hgt(passport: &Passport) -> bool {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"^(\d+)(cm|in)$").unwrap();
-    }
-    let _groups = RE.captures(&passport.hgt);
-    match _groups {
-        None => false,
-        Some(groups) => {
-            let value = match groups.get(1).unwrap().as_str().parse::<u32>() {
-                Ok(x) => x,
-                Err(_) => return false,
-            };
-            let unit = groups.get(2).unwrap().as_str();
-            match unit {
-                "cm" => (150..=193).contains(&value),
-                "in" => (59..=76).contains(&value),
-                _ => false,
-            }
-        }
-    }
+/// Reads the schema at `path`, or the built in one if there is no path.
+fn read_schema(path: Option<&Path>) -> anyhow::Result<Schema> {
+    let schema = match path {
+        Some(path) => Schema::parse(&input::read_source(path)?)?,
+        None => Schema::parse(DEFAULT_SCHEMA)?,
+    };
+    Ok(schema)
 }
 
-fn validate_hcl(passport: &Passport) -> bool {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"^#[0-9a-f]{6}$").unwrap();
+/// Checks that `lines` parse as a batch of passports, and that converting each of them to a
+/// typed `Passport` with the built in schema either succeeds or reports why not.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    let schema = read_schema(None)?;
+    for record in parse_passports(lines)? {
+        let _ = Passport::new(&schema, &record.fields);
     }
-    RE.is_match(&passport.hcl)
+    Ok(())
 }
 
-fn validate_ecl(passport: &Passport) -> bool {
-    matches!(
-        passport.ecl.as_str(),
-        "amb" | "blu" | "brn" | "gry" | "grn" | "hzl" | "oth"
-    )
+/// Solves both parts of the puzzle for the batch of passports in `lines`, with the built in
+/// schema.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let schema = read_schema(None)?;
+    let records = parse_passports(lines)?;
+    count_complete(&schema, &records);
+    count_valid(&schema, &records)?;
+    Ok(())
 }
 
-fn validate_pid(passport: &Passport) -> bool {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"^[0-9]{9}$").unwrap();
-    }
-    RE.is_match(&passport.pid)
+/// Checks that `text` parses as a passport schema.
+pub(crate) fn check_schema(text: &str) -> anyhow::Result<()> {
+    Schema::parse(text)?;
+    Ok(())
 }
@@ -0,0 +1,387 @@
+//! A strongly typed passport, converted from the raw `key:value` fields of a record once a
+//! schema accepts them.
+//!
+//! Each field type only checks the shape of its value; range rules such as which birth years
+//! are acceptable live in the schema.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::schema::{FieldError, Schema};
+
+#[derive(Debug, PartialEq)]
+pub struct ParseFieldError {
+    kind: &'static str,
+    value: String,
+}
+
+impl ParseFieldError {
+    fn new(kind: &'static str, value: &str) -> ParseFieldError {
+        ParseFieldError {
+            kind,
+            value: value.to_string(),
+        }
+    }
+}
+
+impl std::error::Error for ParseFieldError {}
+
+impl std::fmt::Display for ParseFieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "`{}` is not a valid {}", self.value, self.kind)
+    }
+}
+
+/// Returns `s` parsed as a number if it is made of exactly `digits` decimal digits.
+fn parse_digits(s: &str, digits: usize) -> Option<u32> {
+    if s.len() == digits && s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// A four digit year.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Year(u32);
+
+impl FromStr for Year {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<Year, ParseFieldError> {
+        parse_digits(s, 4)
+            .map(Year)
+            .ok_or_else(|| ParseFieldError::new("year", s))
+    }
+}
+
+impl std::fmt::Display for Year {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:04}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthUnit {
+    Cm,
+    In,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Height {
+    value: u32,
+    unit: LengthUnit,
+}
+
+impl FromStr for Height {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<Height, ParseFieldError> {
+        let error = || ParseFieldError::new("height", s);
+        let (value, unit) = if let Some(value) = s.strip_suffix("cm") {
+            (value, LengthUnit::Cm)
+        } else if let Some(value) = s.strip_suffix("in") {
+            (value, LengthUnit::In)
+        } else {
+            return Err(error());
+        };
+
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(error());
+        }
+        Ok(Height {
+            value: value.parse().map_err(|_| error())?,
+            unit,
+        })
+    }
+}
+
+impl std::fmt::Display for Height {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.unit {
+            LengthUnit::Cm => write!(f, "{}cm", self.value),
+            LengthUnit::In => write!(f, "{}in", self.value),
+        }
+    }
+}
+
+/// A colour written as `#` followed by six lowercase hexadecimal digits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HexColor(u32);
+
+impl FromStr for HexColor {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<HexColor, ParseFieldError> {
+        match s.strip_prefix('#') {
+            Some(hex)
+                if hex.len() == 6
+                    && hex
+                        .bytes()
+                        .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b)) =>
+            {
+                u32::from_str_radix(hex, 16)
+                    .map(HexColor)
+                    .map_err(|_| ParseFieldError::new("hex color", s))
+            }
+            _ => Err(ParseFieldError::new("hex color", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for HexColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "#{:06x}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EyeColor {
+    Amber,
+    Blue,
+    Brown,
+    Gray,
+    Green,
+    Hazel,
+    Other,
+}
+
+impl FromStr for EyeColor {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<EyeColor, ParseFieldError> {
+        match s {
+            "amb" => Ok(EyeColor::Amber),
+            "blu" => Ok(EyeColor::Blue),
+            "brn" => Ok(EyeColor::Brown),
+            "gry" => Ok(EyeColor::Gray),
+            "grn" => Ok(EyeColor::Green),
+            "hzl" => Ok(EyeColor::Hazel),
+            "oth" => Ok(EyeColor::Other),
+            _ => Err(ParseFieldError::new("eye color", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for EyeColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            EyeColor::Amber => "amb",
+            EyeColor::Blue => "blu",
+            EyeColor::Brown => "brn",
+            EyeColor::Gray => "gry",
+            EyeColor::Green => "grn",
+            EyeColor::Hazel => "hzl",
+            EyeColor::Other => "oth",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A nine digit passport number, including any leading zeros.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PassportId(u32);
+
+impl FromStr for PassportId {
+    type Err = ParseFieldError;
+
+    fn from_str(s: &str) -> Result<PassportId, ParseFieldError> {
+        parse_digits(s, 9)
+            .map(PassportId)
+            .ok_or_else(|| ParseFieldError::new("passport id", s))
+    }
+}
+
+impl std::fmt::Display for PassportId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:09}", self.0)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PassportError {
+    /// The schema rejects the passport, for the given reasons.
+    Rejected(Vec<FieldError>),
+    /// The schema accepts the field, but its value doesn't have the field's type.
+    Malformed {
+        field: &'static str,
+        source: ParseFieldError,
+    },
+}
+
+impl std::error::Error for PassportError {}
+
+impl std::fmt::Display for PassportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PassportError::Rejected(errors) => write!(
+                f,
+                "{}",
+                errors
+                    .iter()
+                    .map(|error| error.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ),
+            PassportError::Malformed { field, source } => write!(f, "{}: {}", field, source),
+        }
+    }
+}
+
+/// A passport which a schema accepts. Which fields must be present is up to the schema, so
+/// every field is `None` when the passport leaves it out.
+#[derive(Debug, PartialEq)]
+pub struct Passport {
+    pub byr: Option<Year>,
+    pub iyr: Option<Year>,
+    pub eyr: Option<Year>,
+    pub hgt: Option<Height>,
+    pub hcl: Option<HexColor>,
+    pub ecl: Option<EyeColor>,
+    pub pid: Option<PassportId>,
+    pub cid: Option<String>,
+}
+
+impl Passport {
+    /// Checks `fields` against `schema` and converts them to their types. Fields which the
+    /// schema doesn't know about are ignored rather than rejected.
+    pub fn new(schema: &Schema, fields: &HashMap<&str, &str>) -> Result<Passport, PassportError> {
+        let errors = schema
+            .validate(fields)
+            .into_iter()
+            .filter(|error| !matches!(error, FieldError::Unexpected { .. }))
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(PassportError::Rejected(errors));
+        }
+
+        fn field<T: FromStr<Err = ParseFieldError>>(
+            fields: &HashMap<&str, &str>,
+            field: &'static str,
+        ) -> Result<Option<T>, PassportError> {
+            fields
+                .get(field)
+                .map(|value| value.parse())
+                .transpose()
+                .map_err(|source| PassportError::Malformed { field, source })
+        }
+
+        Ok(Passport {
+            byr: field(fields, "byr")?,
+            iyr: field(fields, "iyr")?,
+            eyr: field(fields, "eyr")?,
+            hgt: field(fields, "hgt")?,
+            hcl: field(fields, "hcl")?,
+            ecl: field(fields, "ecl")?,
+            pid: field(fields, "pid")?,
+            cid: fields.get("cid").map(|cid| cid.to_string()),
+        })
+    }
+}
+
+impl std::fmt::Display for Passport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let fields = [
+            ("byr", self.byr.map(|byr| byr.to_string())),
+            ("iyr", self.iyr.map(|iyr| iyr.to_string())),
+            ("eyr", self.eyr.map(|eyr| eyr.to_string())),
+            ("hgt", self.hgt.map(|hgt| hgt.to_string())),
+            ("hcl", self.hcl.map(|hcl| hcl.to_string())),
+            ("ecl", self.ecl.map(|ecl| ecl.to_string())),
+            ("pid", self.pid.map(|pid| pid.to_string())),
+            ("cid", self.cid.clone()),
+        ];
+        let fields = fields
+            .iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| format!("{}:{}", name, value)))
+            .collect::<Vec<_>>();
+        write!(f, "{}", fields.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(passport: &str) -> HashMap<&str, &str> {
+        passport
+            .split_whitespace()
+            .map(|field| field.split_once(':').unwrap())
+            .collect()
+    }
+
+    fn passport(schema: &str, passport: &str) -> Result<Passport, PassportError> {
+        Passport::new(&Schema::parse(schema).unwrap(), &fields(passport))
+    }
+
+    #[test]
+    fn test_valid_passport() {
+        let schema = include_str!("../../passport_schema.txt");
+        let passport = passport(
+            schema,
+            "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f xyz:1",
+        )
+        .unwrap();
+        assert_eq!(passport.byr, Some(Year(1980)));
+        assert_eq!(
+            passport.hgt,
+            Some(Height {
+                value: 74,
+                unit: LengthUnit::In
+            })
+        );
+        assert_eq!(passport.hcl, Some(HexColor(0x623a2f)));
+        assert_eq!(passport.ecl, Some(EyeColor::Green));
+        assert_eq!(passport.pid, Some(PassportId(87499704)));
+        assert_eq!(passport.cid, None);
+        assert_eq!(
+            passport.to_string(),
+            "byr:1980 iyr:2012 eyr:2030 hgt:74in hcl:#623a2f ecl:grn pid:087499704"
+        );
+    }
+
+    #[test]
+    fn test_invalid_passport() {
+        let schema = include_str!("../../passport_schema.txt");
+        let error = passport(
+            schema,
+            "hcl:dab227 iyr:2012 ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            PassportError::Rejected(vec![FieldError::Invalid {
+                field: "hcl".to_string(),
+                value: "dab227".to_string(),
+                reason: "does not match `^#[0-9a-f]{6}$`".to_string(),
+            }])
+        );
+
+        let error = passport(schema, "byr:1980 cid:1").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "iyr: missing; eyr: missing; hgt: missing; hcl: missing; ecl: missing; pid: missing"
+        );
+    }
+
+    #[test]
+    fn test_schema_decides_which_fields_are_required() {
+        let passport = passport("byr required any\nhgt optional any", "byr:1980").unwrap();
+        assert_eq!(passport.byr, Some(Year(1980)));
+        assert_eq!(passport.hgt, None);
+        assert_eq!(passport.to_string(), "byr:1980");
+    }
+
+    #[test]
+    fn test_malformed_passport() {
+        let error = passport("hgt required any", "hgt:tall").unwrap_err();
+        assert_eq!(
+            error,
+            PassportError::Malformed {
+                field: "hgt",
+                source: ParseFieldError::new("height", "tall"),
+            }
+        );
+        assert_eq!(error.to_string(), "hgt: `tall` is not a valid height");
+    }
+}
@@ -0,0 +1,427 @@
+//! Declarative passport validation rules, loaded from a text file.
+//!
+//! Each non-blank line of a schema which isn't a `#` comment describes one field:
+//!
+//! ```text
+//! <name> required|optional <rule> [<argument>...]
+//! ```
+//!
+//! where the rule is one of
+//!
+//! * `any`: every value is valid,
+//! * `year <min> <max>`: four digits, between `min` and `max` inclusive,
+//! * `range <min> <max>`: a number between `min` and `max` inclusive,
+//! * `height <unit> <min> <max> [<unit> <min> <max>...]`: a number immediately followed by
+//!   one of the units, between that unit's `min` and `max` inclusive,
+//! * `regex <pattern>`: matches the pattern, which may contain spaces,
+//! * `enum <value>...`: exactly one of the values.
+
+use regex::Regex;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct SchemaError {
+    line: usize,
+    message: String,
+}
+
+impl std::error::Error for SchemaError {}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "schema line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug)]
+enum Rule {
+    Any,
+    Year { min: u32, max: u32 },
+    Range { min: u32, max: u32 },
+    Height { units: Vec<(String, u32, u32)> },
+    Regex(Regex),
+    Enum(Vec<String>),
+}
+
+impl Rule {
+    fn parse(name: &str, arguments: &[&str], pattern: &str) -> Result<Rule, String> {
+        let number = |s: &str| {
+            s.parse::<u32>()
+                .map_err(|_| format!("expected a number, found `{}`", s))
+        };
+
+        match (name, arguments) {
+            ("any", []) => Ok(Rule::Any),
+            ("year", [min, max]) => Ok(Rule::Year {
+                min: number(min)?,
+                max: number(max)?,
+            }),
+            ("range", [min, max]) => Ok(Rule::Range {
+                min: number(min)?,
+                max: number(max)?,
+            }),
+            ("height", units) if !units.is_empty() && units.len() % 3 == 0 => Ok(Rule::Height {
+                units: units
+                    .chunks(3)
+                    .map(|unit| Ok((unit[0].to_string(), number(unit[1])?, number(unit[2])?)))
+                    .collect::<Result<_, String>>()?,
+            }),
+            ("regex", [_, ..]) => Regex::new(pattern)
+                .map(Rule::Regex)
+                .map_err(|e| e.to_string()),
+            ("enum", values) if !values.is_empty() => {
+                Ok(Rule::Enum(values.iter().map(|s| s.to_string()).collect()))
+            }
+            ("any", _)
+            | ("year", _)
+            | ("range", _)
+            | ("height", _)
+            | ("regex", _)
+            | ("enum", _) => Err(format!("wrong arguments for rule `{}`", name)),
+            _ => Err(format!("unknown rule `{}`", name)),
+        }
+    }
+
+    /// Returns a description of why `value` breaks the rule, if it does.
+    fn check(&self, value: &str) -> Option<String> {
+        match self {
+            Rule::Any => None,
+            Rule::Year { min, max } => {
+                if value.len() != 4 || !value.bytes().all(|b| b.is_ascii_digit()) {
+                    Some("is not a four digit year".to_string())
+                } else {
+                    check_range(value, *min, *max, "")
+                }
+            }
+            Rule::Range { min, max } => check_range(value, *min, *max, ""),
+            Rule::Height { units } => {
+                let split = value
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(value.len());
+                let (number, unit) = value.split_at(split);
+                match units.iter().find(|(name, _, _)| name == unit) {
+                    Some((name, min, max)) => check_range(number, *min, *max, name),
+                    None => Some(format!(
+                        "has unknown unit `{}`, expected one of {}",
+                        unit,
+                        units
+                            .iter()
+                            .map(|(name, _, _)| format!("`{}`", name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+                }
+            }
+            Rule::Regex(regex) => {
+                if regex.is_match(value) {
+                    None
+                } else {
+                    Some(format!("does not match `{}`", regex))
+                }
+            }
+            Rule::Enum(values) => {
+                if values.iter().any(|v| v == value) {
+                    None
+                } else {
+                    Some(format!("is not one of {}", values.join(", ")))
+                }
+            }
+        }
+    }
+}
+
+fn check_range(value: &str, min: u32, max: u32, unit: &str) -> Option<String> {
+    match value.parse::<u32>() {
+        Ok(x) if (min..=max).contains(&x) => None,
+        Ok(_) => Some(format!("is outside of {}{} to {}{}", min, unit, max, unit)),
+        Err(_) => Some("is not a number".to_string()),
+    }
+}
+
+#[derive(Debug)]
+struct FieldSpec {
+    name: String,
+    required: bool,
+    rule: Rule,
+}
+
+/// A problem with one field of a passport.
+#[derive(Debug, PartialEq)]
+pub enum FieldError {
+    Missing {
+        field: String,
+    },
+    Unexpected {
+        field: String,
+    },
+    Invalid {
+        field: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl FieldError {
+    /// Whether the error is a required field which is missing. Fields which the schema
+    /// doesn't know about are reported, but don't count as missing anything.
+    pub fn is_missing(&self) -> bool {
+        matches!(self, FieldError::Missing { .. })
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FieldError::Missing { field } => write!(f, "{}: missing", field),
+            FieldError::Unexpected { field } => write!(f, "{}: not in the schema", field),
+            FieldError::Invalid {
+                field,
+                value,
+                reason,
+            } => write!(f, "{}: `{}` {}", field, value, reason),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Schema {
+    fields: Vec<FieldSpec>,
+}
+
+impl Schema {
+    pub fn parse(text: &str) -> Result<Schema, SchemaError> {
+        let mut fields = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| SchemaError {
+                line: i + 1,
+                message,
+            };
+
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            let (name, presence, rule, arguments) = match tokens.as_slice() {
+                [name, presence, rule, arguments @ ..] => (*name, *presence, *rule, arguments),
+                _ => return Err(error("expected `<name> <presence> <rule>`".to_string())),
+            };
+
+            let required = match presence {
+                "required" => true,
+                "optional" => false,
+                _ => return Err(error(format!("unknown presence `{}`", presence))),
+            };
+
+            let mut pattern = line;
+            for _ in 0..3 {
+                pattern = pattern.trim_start();
+                pattern = &pattern[pattern.find(char::is_whitespace).unwrap_or(pattern.len())..];
+            }
+            let pattern = pattern.trim();
+            let rule = Rule::parse(rule, arguments, pattern).map_err(error)?;
+
+            fields.push(FieldSpec {
+                name: name.to_string(),
+                required,
+                rule,
+            });
+        }
+
+        Ok(Schema { fields })
+    }
+
+    /// Checks every field of a passport against the schema, returning all problems found in
+    /// schema order, followed by any fields the schema doesn't know about.
+    pub fn validate(&self, record: &HashMap<&str, &str>) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        for spec in &self.fields {
+            match record.get(spec.name.as_str()) {
+                None if spec.required => errors.push(FieldError::Missing {
+                    field: spec.name.clone(),
+                }),
+                None => {}
+                Some(value) => {
+                    if let Some(reason) = spec.rule.check(value) {
+                        errors.push(FieldError::Invalid {
+                            field: spec.name.clone(),
+                            value: value.to_string(),
+                            reason,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut unexpected = record
+            .keys()
+            .filter(|key| !self.fields.iter().any(|spec| spec.name == **key))
+            .collect::<Vec<_>>();
+        unexpected.sort_unstable();
+        errors.extend(unexpected.into_iter().map(|key| FieldError::Unexpected {
+            field: key.to_string(),
+        }));
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::parse(include_str!("../../passport_schema.txt")).unwrap()
+    }
+
+    fn fields(passport: &str) -> HashMap<&str, &str> {
+        passport
+            .split_whitespace()
+            .map(|field| field.split_once(':').unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let schema = schema();
+        let names = schema
+            .fields
+            .iter()
+            .map(|spec| (spec.name.as_str(), spec.required))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("byr", true),
+                ("iyr", true),
+                ("eyr", true),
+                ("hgt", true),
+                ("hcl", true),
+                ("ecl", true),
+                ("pid", true),
+                ("cid", false)
+            ]
+        );
+
+        // Comments and blank lines are skipped, and a pattern keeps its inner spaces.
+        let schema = Schema::parse("# names\n\n  name optional regex ^[a-z]+ [a-z]+$  \n").unwrap();
+        assert_eq!(schema.fields.len(), 1);
+        let name = |value| vec![("name", value)].into_iter().collect();
+        assert_eq!(schema.validate(&name("ada lovelace")), []);
+        assert_eq!(schema.validate(&name("ada")).len(), 1);
+        assert_eq!(schema.validate(&HashMap::new()), []);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text| Schema::parse(text).unwrap_err().to_string();
+        assert_eq!(
+            error("byr required year 1920 2002\nbyr\n"),
+            "schema line 2: expected `<name> <presence> <rule>`"
+        );
+        assert_eq!(
+            error("byr sometimes any"),
+            "schema line 1: unknown presence `sometimes`"
+        );
+        assert_eq!(
+            error("byr required year 1920"),
+            "schema line 1: wrong arguments for rule `year`"
+        );
+        assert_eq!(
+            error("byr required year 1920 later"),
+            "schema line 1: expected a number, found `later`"
+        );
+        assert_eq!(
+            error("byr required date"),
+            "schema line 1: unknown rule `date`"
+        );
+        assert!(error("hcl required regex [").starts_with("schema line 1: "));
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = schema();
+        assert_eq!(
+            schema.validate(&fields(
+                "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f"
+            )),
+            []
+        );
+        assert_eq!(
+            schema.validate(&fields(
+                "eyr:1972 cid:100 hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926"
+            )),
+            [
+                FieldError::Invalid {
+                    field: "eyr".to_string(),
+                    value: "1972".to_string(),
+                    reason: "is outside of 2020 to 2030".to_string(),
+                },
+                FieldError::Invalid {
+                    field: "hgt".to_string(),
+                    value: "170".to_string(),
+                    reason: "has unknown unit ``, expected one of `cm`, `in`".to_string(),
+                },
+                FieldError::Invalid {
+                    field: "pid".to_string(),
+                    value: "186cm".to_string(),
+                    reason: "does not match `^[0-9]{9}$`".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            schema.validate(&fields("hgt:59cm ecl:zzz byr:20x2 pid:1")),
+            [
+                FieldError::Invalid {
+                    field: "byr".to_string(),
+                    value: "20x2".to_string(),
+                    reason: "is not a four digit year".to_string(),
+                },
+                FieldError::Missing {
+                    field: "iyr".to_string()
+                },
+                FieldError::Missing {
+                    field: "eyr".to_string()
+                },
+                FieldError::Invalid {
+                    field: "hgt".to_string(),
+                    value: "59cm".to_string(),
+                    reason: "is outside of 150cm to 193cm".to_string(),
+                },
+                FieldError::Missing {
+                    field: "hcl".to_string()
+                },
+                FieldError::Invalid {
+                    field: "ecl".to_string(),
+                    value: "zzz".to_string(),
+                    reason: "is not one of amb, blu, brn, gry, grn, hzl, oth".to_string(),
+                },
+                FieldError::Invalid {
+                    field: "pid".to_string(),
+                    value: "1".to_string(),
+                    reason: "does not match `^[0-9]{9}$`".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_fields_are_not_missing() {
+        let errors = schema().validate(&fields(
+            "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f zzz:1 aaa:2",
+        ));
+        assert_eq!(
+            errors,
+            [
+                FieldError::Unexpected {
+                    field: "aaa".to_string()
+                },
+                FieldError::Unexpected {
+                    field: "zzz".to_string()
+                },
+            ]
+        );
+        assert!(errors.iter().all(|error| !error.is_missing()));
+        assert_eq!(errors[0].to_string(), "aaa: not in the schema");
+    }
+}
@@ -1,127 +1,375 @@
 use anyhow::Result;
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::convert::TryInto;
-use std::fs::File;
-use std::io::{self, prelude::*};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::path::Path;
 
-struct BoardingPass {
-    row: [char; 7],
-    seat: [char; 3],
+use crate::generate::check_size;
+use crate::input::read_lines;
+
+/// The largest number of characters a boarding pass may have, which keeps seat maps to a
+/// sensible size.
+const MAX_SEAT_BITS: u32 = 24;
+
+#[derive(Debug)]
+struct ParseBoardingPassError {
+    pass: String,
 }
 
-pub fn problem1() -> anyhow::Result<()> {
-    let path = Path::new("input_day5.txt");
-    let display = path.display();
+impl std::error::Error for ParseBoardingPassError {}
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+impl std::fmt::Display for ParseBoardingPassError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Failed to parse boarding pass `{}`.", self.pass)
+    }
+}
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+#[derive(Debug)]
+struct SeatOutOfRangeError {
+    seat_id: u32,
+}
 
-    let boarding_passes = parse_boarding_passes(&lines)?;
-    let seat_numbers = boarding_passes
-        .iter()
-        .map(compute_seat_number)
-        .collect::<Result<Vec<u32>>>()?;
-    let max_seat_number = seat_numbers.iter().max().unwrap();
-    println!("{}", max_seat_number);
-    Ok(())
+impl std::error::Error for SeatOutOfRangeError {}
+
+impl std::fmt::Display for SeatOutOfRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Seat {} does not exist on this plane.", self.seat_id)
+    }
 }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day5.txt");
-    let display = path.display();
+#[derive(Debug)]
+struct InvalidGeometryError;
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+impl std::error::Error for InvalidGeometryError {}
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+impl std::fmt::Display for InvalidGeometryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "A plane must have between 1 and {} seat bits.",
+            MAX_SEAT_BITS
+        )
+    }
+}
 
-    let boarding_passes = parse_boarding_passes(&lines)?;
-    let mut seat_numbers = boarding_passes
-        .iter()
-        .map(compute_seat_number)
-        .collect::<Result<Vec<u32>>>()?;
-    seat_numbers.sort_unstable();
+#[derive(Debug)]
+struct ProblemInvariantError;
 
-    let empty_seat = seat_numbers
-        .iter()
-        .zip(seat_numbers[1..].iter())
-        .find(|(x, y)| *y - *x != 1)
-        .unwrap()
-        .0
-        + 1;
-    println!("{}", empty_seat);
-    Ok(())
+impl std::error::Error for ProblemInvariantError {}
+
+impl std::fmt::Display for ProblemInvariantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "An invariant of the Advent of Code challenge seems to have been violated."
+        )
+    }
 }
 
-fn parse_boarding_passes(lines: &[String]) -> anyhow::Result<Vec<BoardingPass>> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"^([FB]{7})([LR]{3})$").unwrap();
+/// The layout of a plane, as the number of characters of a boarding pass which select the
+/// row (`F`/`B`) and the column (`L`/`R`).
+#[derive(Debug, Clone, Copy)]
+pub struct PlaneGeometry {
+    row_bits: u32,
+    column_bits: u32,
+}
+
+impl Default for PlaneGeometry {
+    fn default() -> Self {
+        Self {
+            row_bits: 7,
+            column_bits: 3,
+        }
     }
+}
 
-    Ok(lines
-        .iter()
-        .map(|line| RE.captures(&line).unwrap()) // TODO: Remove unwrap and return error
-        .map(|groups| BoardingPass {
-            row: groups
-                .get(1)
-                .unwrap()
-                .as_str()
-                .chars()
-                .collect::<Vec<char>>()
-                .try_into()
-                .unwrap(),
-            seat: groups
-                .get(2)
-                .unwrap()
-                .as_str()
-                .chars()
-                .collect::<Vec<char>>()
-                .try_into()
-                .unwrap(),
+impl PlaneGeometry {
+    pub fn new(row_bits: u32, column_bits: u32) -> anyhow::Result<PlaneGeometry> {
+        let bits = row_bits.saturating_add(column_bits);
+        if bits == 0 || bits > MAX_SEAT_BITS {
+            return Err(InvalidGeometryError.into());
+        }
+        Ok(PlaneGeometry {
+            row_bits,
+            column_bits,
+        })
+    }
+
+    fn rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    fn columns(&self) -> u32 {
+        1 << self.column_bits
+    }
+
+    fn seats(&self) -> u32 {
+        self.rows() * self.columns()
+    }
+}
+
+/// Converts between boarding passes and seat IDs.
+///
+/// A boarding pass is a binary number, most significant bit first, with `B` and `R` as ones
+/// and `F` and `L` as zeros. Reading the whole pass at once gives `row * columns + column`,
+/// which is exactly the seat ID.
+#[derive(Debug, Clone, Copy, Default)]
+struct BoardingPassCodec {
+    geometry: PlaneGeometry,
+}
+
+impl BoardingPassCodec {
+    fn decode(&self, pass: &str) -> Result<u32, ParseBoardingPassError> {
+        let error = || ParseBoardingPassError {
+            pass: pass.to_string(),
+        };
+        let row_bits = self.geometry.row_bits as usize;
+        let column_bits = self.geometry.column_bits as usize;
+        if pass.len() != row_bits + column_bits {
+            return Err(error());
+        }
+
+        pass.chars().enumerate().try_fold(0, |seat_id, (i, c)| {
+            let bit = match (c, i < row_bits) {
+                ('F', true) | ('L', false) => 0,
+                ('B', true) | ('R', false) => 1,
+                _ => return Err(error()),
+            };
+            Ok(seat_id << 1 | bit)
         })
-        .collect())
+    }
+
+    fn encode(&self, seat_id: u32) -> Result<String, SeatOutOfRangeError> {
+        if seat_id >= self.geometry.seats() {
+            return Err(SeatOutOfRangeError { seat_id });
+        }
+
+        let bits = self.geometry.row_bits + self.geometry.column_bits;
+        Ok((0..bits)
+            .rev()
+            .map(|i| {
+                let bit = seat_id >> i & 1 == 1;
+                match (i >= self.geometry.column_bits, bit) {
+                    (true, false) => 'F',
+                    (true, true) => 'B',
+                    (false, false) => 'L',
+                    (false, true) => 'R',
+                }
+            })
+            .collect())
+    }
+}
+
+/// Finds every empty seat whose neighbouring seat IDs are both taken.
+fn find_gaps(taken: &[bool]) -> Vec<u32> {
+    (1..taken.len().saturating_sub(1))
+        .filter(|&i| !taken[i] && taken[i - 1] && taken[i + 1])
+        .map(|i| i as u32)
+        .collect()
 }
 
-fn compute_seat_number(boarding_pass: &BoardingPass) -> anyhow::Result<u32> {
-    let mut row_bounds = (0, 127);
-    for c in boarding_pass.row.iter() {
-        row_bounds = binary_search_row(*c, row_bounds);
+/// Draws one row of seats per line, with taken seats as `#`, gaps between taken seats as
+/// `O` and other empty seats as `.`, followed by the IDs of every empty seat between the
+/// first and last taken seat.
+fn render_seat_map(geometry: PlaneGeometry, taken: &[bool]) -> String {
+    let first = taken.iter().position(|&t| t);
+    let last = taken.iter().rposition(|&t| t);
+    let between = |i: usize| matches!((first, last), (Some(f), Some(l)) if f < i && i < l);
+
+    let mut output = String::new();
+    for row in 0..geometry.rows() as usize {
+        output += &format!("{:4} ", row);
+        for column in 0..geometry.columns() as usize {
+            let i = row * geometry.columns() as usize + column;
+            output.push(match (taken[i], between(i)) {
+                (true, _) => '#',
+                (false, true) => 'O',
+                (false, false) => '.',
+            });
+        }
+        output.push('\n');
     }
-    assert_eq!(row_bounds.0, row_bounds.1);
 
-    let mut seat_bounds = (0, 7);
-    for c in boarding_pass.seat.iter() {
-        seat_bounds = binary_search_seat(*c, seat_bounds);
+    let empty = (0..taken.len())
+        .filter(|&i| !taken[i] && between(i))
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>();
+    output += &format!("empty seats: {}\n", empty.join(" "));
+    output
+}
+
+pub fn problem1() -> anyhow::Result<u32> {
+    let codec = BoardingPassCodec::default();
+    let lines = read_lines(Path::new("input_day5.txt"))?;
+    Ok(highest_seat_id(&decode_all(&codec, &lines)?)?)
+}
+
+pub fn problem2() -> anyhow::Result<u32> {
+    let codec = BoardingPassCodec::default();
+    let lines = read_lines(Path::new("input_day5.txt"))?;
+    Ok(empty_seat_id(&codec, &decode_all(&codec, &lines)?)?)
+}
+
+fn highest_seat_id(seat_ids: &[u32]) -> Result<u32, ProblemInvariantError> {
+    seat_ids.iter().max().copied().ok_or(ProblemInvariantError)
+}
+
+/// The first empty seat whose neighbouring seat IDs are both taken.
+fn empty_seat_id(
+    codec: &BoardingPassCodec,
+    seat_ids: &[u32],
+) -> Result<u32, ProblemInvariantError> {
+    let taken = taken_seats(codec, seat_ids);
+    find_gaps(&taken)
+        .first()
+        .copied()
+        .ok_or(ProblemInvariantError)
+}
+
+/// Prints the boarding pass for `seat_id`.
+pub fn encode(seat_id: u32, geometry: PlaneGeometry) -> anyhow::Result<()> {
+    println!("{}", BoardingPassCodec { geometry }.encode(seat_id)?);
+    Ok(())
+}
+
+/// Prints the seat ID of `pass`.
+pub fn decode(pass: &str, geometry: PlaneGeometry) -> anyhow::Result<()> {
+    println!("{}", BoardingPassCodec { geometry }.decode(pass)?);
+    Ok(())
+}
+
+/// Prints a map of the seats taken by the boarding passes at `path`.
+pub fn seat_map(path: &Path, geometry: PlaneGeometry) -> anyhow::Result<()> {
+    let codec = BoardingPassCodec { geometry };
+    let lines = read_lines(path)?;
+    let taken = taken_seats(&codec, &decode_all(&codec, &lines)?);
+    print!("{}", render_seat_map(geometry, &taken));
+    Ok(())
+}
+
+/// `size` random boarding passes for a full flight of consecutive seats, except for one
+/// seat in the middle.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    let codec = BoardingPassCodec::default();
+    let seats = codec.geometry.seats();
+    check_size(size, 2..=seats as usize - 1)?;
+
+    let size = size as u32;
+    let first = rng.gen_range(0..seats - size);
+    let missing = rng.gen_range(first + 1..first + size);
+    let mut seat_ids = (first..=first + size)
+        .filter(|&seat_id| seat_id != missing)
+        .collect::<Vec<_>>();
+    seat_ids.shuffle(rng);
+
+    let mut input = String::new();
+    for seat_id in seat_ids {
+        input += &codec.encode(seat_id)?;
+        input.push('\n');
     }
-    assert_eq!(seat_bounds.0, seat_bounds.1);
+    Ok(input)
+}
 
-    Ok(row_bounds.0 * 8 + seat_bounds.0)
+fn decode_all<S: AsRef<str>>(codec: &BoardingPassCodec, lines: &[S]) -> anyhow::Result<Vec<u32>> {
+    let seat_ids = lines
+        .iter()
+        .map(|line| codec.decode(line.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(seat_ids)
 }
 
-fn binary_search_row(c: char, (lower, upper): (u32, u32)) -> (u32, u32) {
-    match c {
-        'F' => (lower, lower + ((upper - lower) / 2)),
-        'B' => (lower + ((upper - lower + 1) / 2), upper),
-        _ => panic!("Unexpected character {}.", c),
+fn taken_seats(codec: &BoardingPassCodec, seat_ids: &[u32]) -> Vec<bool> {
+    let mut taken = vec![false; codec.geometry.seats() as usize];
+    for &seat_id in seat_ids {
+        taken[seat_id as usize] = true;
     }
+    taken
+}
+
+/// Checks that `lines` parse as boarding passes for the default plane.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    decode_all(&BoardingPassCodec::default(), lines)?;
+    Ok(())
+}
+
+/// Solves both parts of the puzzle for the boarding passes in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let codec = BoardingPassCodec::default();
+    let seat_ids = decode_all(&codec, lines)?;
+    highest_seat_id(&seat_ids)?;
+    empty_seat_id(&codec, &seat_ids)?;
+    Ok(())
 }
 
-fn binary_search_seat(c: char, (lower, upper): (u32, u32)) -> (u32, u32) {
-    match c {
-        'L' => (lower, lower + ((upper - lower) / 2)),
-        'R' => (lower + ((upper - lower + 1) / 2), upper),
-        _ => panic!("Unexpected character {}.", c),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codec(row_bits: u32, column_bits: u32) -> BoardingPassCodec {
+        BoardingPassCodec {
+            geometry: PlaneGeometry::new(row_bits, column_bits).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_examples() {
+        let codec = BoardingPassCodec::default();
+        for &(pass, seat_id) in &[
+            ("FBFBBFFRLR", 357),
+            ("BFFFBBFRRR", 567),
+            ("FFFBBBFRRR", 119),
+            ("BBFFBBFRLL", 820),
+        ] {
+            assert_eq!(codec.decode(pass).unwrap(), seat_id);
+            assert_eq!(codec.encode(seat_id).unwrap(), pass);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &(row_bits, column_bits) in &[(7, 3), (0, 1), (1, 0), (4, 2), (2, 5), (12, 12)] {
+            let codec = codec(row_bits, column_bits);
+            let seats = codec.geometry.seats();
+            let step = (seats / 1000).max(1) as usize;
+            for seat_id in (0..seats).step_by(step).chain(std::iter::once(seats - 1)) {
+                let pass = codec.encode(seat_id).unwrap();
+                assert_eq!(pass.len() as u32, row_bits + column_bits);
+                assert_eq!(codec.decode(&pass).unwrap(), seat_id);
+            }
+            assert!(codec.encode(seats).is_err());
+        }
+    }
+
+    #[test]
+    fn test_geometry() {
+        let codec = codec(2, 1);
+        assert_eq!(codec.encode(5).unwrap(), "BFR");
+        assert_eq!(codec.decode("FBL").unwrap(), 2);
+        assert!(codec.decode("FBLR").is_err());
+        assert!(codec.decode("FRL").is_err());
+        assert!(codec.decode("FBB").is_err());
+        assert_eq!(
+            codec.encode(8).unwrap_err().to_string(),
+            "Seat 8 does not exist on this plane."
+        );
+
+        assert!(PlaneGeometry::new(0, 0).is_err());
+        assert!(PlaneGeometry::new(20, 5).is_err());
+        assert!(PlaneGeometry::new(u32::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn test_seat_map() {
+        let codec = codec(2, 2);
+        let seat_ids = ["FBLR", "FBRL", "BFLL", "BFLR", "BFRR"]
+            .iter()
+            .map(|pass| codec.decode(pass).unwrap())
+            .collect::<Vec<_>>();
+        let taken = taken_seats(&codec, &seat_ids);
+        assert_eq!(find_gaps(&taken), [7, 10]);
+        assert_eq!(
+            render_seat_map(codec.geometry, &taken),
+            "   0 ....\n   1 .##O\n   2 ##O#\n   3 ....\nempty seats: 7 10\n"
+        );
     }
 }
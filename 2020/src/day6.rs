@@ -1,78 +1,280 @@
 use anyhow::Result;
-use std::collections::HashSet;
-use std::fs::File;
-use std::io::{self, prelude::*};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::path::Path;
 
-pub fn problem1() -> anyhow::Result<()> {
-    let path = Path::new("input_day6.txt");
-    let display = path.display();
+use crate::generate::check_size;
+use crate::input::read_lines;
+use crate::records;
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+#[derive(Debug)]
+struct ParseAnswersError {
+    line: String,
+}
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+impl std::error::Error for ParseAnswersError {}
 
-    let forms = problem1_parse_forms(&lines);
-    let sum: usize = forms.iter().map(|f| f.len()).sum();
-    println!("{}", sum);
-    Ok(())
+impl std::fmt::Display for ParseAnswersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Failed to parse answers `{}`.", self.line)
+    }
+}
+
+/// A set of the questions `a` to `z`, one bit per question.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct AnswerSet(u32);
+
+impl AnswerSet {
+    const ALL: AnswerSet = AnswerSet((1 << 26) - 1);
+
+    fn union(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 | other.0)
+    }
+
+    fn intersection(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 & other.0)
+    }
+
+    fn symmetric_difference(self, other: AnswerSet) -> AnswerSet {
+        AnswerSet(self.0 ^ other.0)
+    }
+
+    fn contains(self, question: u32) -> bool {
+        self.0 & (1 << question) != 0
+    }
+
+    fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+}
+
+impl std::str::FromStr for AnswerSet {
+    type Err = ParseAnswersError;
+
+    fn from_str(s: &str) -> Result<AnswerSet, ParseAnswersError> {
+        s.bytes().try_fold(AnswerSet::default(), |set, b| {
+            if b.is_ascii_lowercase() {
+                Ok(set.union(AnswerSet(1 << (b - b'a'))))
+            } else {
+                Err(ParseAnswersError {
+                    line: s.to_string(),
+                })
+            }
+        })
+    }
+}
+
+/// The answers of everyone in a group, one set per person.
+#[derive(Debug)]
+struct Group {
+    people: Vec<AnswerSet>,
+}
+
+impl Group {
+    /// The questions anyone in the group answered "yes" to.
+    fn anyone(&self) -> AnswerSet {
+        self.people
+            .iter()
+            .fold(AnswerSet::default(), |acc, &set| acc.union(set))
+    }
+
+    /// The questions everyone in the group answered "yes" to. With nobody in the group to
+    /// disagree, that is every question; parsed groups always have at least one person.
+    fn everyone(&self) -> AnswerSet {
+        self.people
+            .iter()
+            .fold(AnswerSet::ALL, |acc, &set| acc.intersection(set))
+    }
+
+    /// The questions an odd number of people in the group answered "yes" to.
+    fn odd(&self) -> AnswerSet {
+        self.people.iter().fold(AnswerSet::default(), |acc, &set| {
+            acc.symmetric_difference(set)
+        })
+    }
+
+    /// The questions at least `k` people in the group answered "yes" to.
+    fn at_least(&self, k: usize) -> AnswerSet {
+        (0..26)
+            .filter(|&question| {
+                self.people
+                    .iter()
+                    .filter(|set| set.contains(question))
+                    .count()
+                    >= k
+            })
+            .fold(AnswerSet::default(), |acc, question| {
+                acc.union(AnswerSet(1 << question))
+            })
+    }
+}
+
+pub fn problem1() -> anyhow::Result<usize> {
+    let lines = read_lines(Path::new("input_day6.txt"))?;
+    Ok(sum_answers(&parse_groups(&lines)?, Group::anyone))
+}
+
+pub fn problem2() -> anyhow::Result<usize> {
+    let lines = read_lines(Path::new("input_day6.txt"))?;
+    Ok(sum_answers(&parse_groups(&lines)?, Group::everyone))
+}
+
+/// The total number of questions in each group's `answers`.
+fn sum_answers(groups: &[Group], answers: fn(&Group) -> AnswerSet) -> usize {
+    groups.iter().map(|g| answers(g).len()).sum()
 }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day6.txt");
-    let display = path.display();
+/// Prints, for each group at `path` and in total, how many questions were answered "yes" by
+/// anyone, by everyone, by an odd number of people and, if requested, by at least `k` people.
+pub fn summary(path: &Path, k: Option<usize>) -> anyhow::Result<()> {
+    let lines = read_lines(path)?;
+    let groups = parse_groups(&lines)?;
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
+    let counts = |g: &Group| {
+        [
+            g.anyone().len(),
+            g.everyone().len(),
+            g.odd().len(),
+            k.map_or(0, |k| g.at_least(k).len()),
+        ]
+    };
+    let format = |label: String, [anyone, everyone, odd, at_least]: [usize; 4]| {
+        let mut line = format!(
+            "{}: anyone {}, everyone {}, odd {}",
+            label, anyone, everyone, odd
+        );
+        if let Some(k) = k {
+            line += &format!(", at least {} {}", k, at_least);
+        }
+        line
     };
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+    let mut totals = [0; 4];
+    for (i, group) in groups.iter().enumerate() {
+        let group_counts = counts(group);
+        for (total, count) in totals.iter_mut().zip(group_counts.iter()) {
+            *total += count;
+        }
+        let label = format!("group {} ({} people)", i + 1, group.people.len());
+        println!("{}", format(label, group_counts));
+    }
+    println!("{}", format("total".to_string(), totals));
 
-    let forms = problem2_parse_forms(&lines);
-    let sum: usize = forms.iter().map(|f| f.len()).sum();
-    println!("{}", sum);
     Ok(())
 }
 
-// TODO: These ought to take &[&str]
-fn problem1_parse_forms(lines: &[String]) -> Vec<HashSet<char>> {
-    let groups = lines.split(|line| line.is_empty());
-    groups.map(problem1_parse_form).collect()
+/// Random answers for `size` groups of up to five people.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=100_000)?;
+
+    let mut groups = Vec::with_capacity(size);
+    for _ in 0..size {
+        let people = (0..rng.gen_range(1..=5))
+            .map(|_| {
+                let mut answers = ('a'..='z')
+                    .filter(|_| rng.gen_bool(0.3))
+                    .collect::<Vec<_>>();
+                if answers.is_empty() {
+                    answers.push(rng.gen_range('a'..='z'));
+                }
+                answers.shuffle(rng);
+                answers.into_iter().collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        groups.push(people.join("\n"));
+    }
+    Ok(groups.join("\n\n") + "\n")
 }
 
-fn problem1_parse_form(group: &[String]) -> HashSet<char> {
-    group
-        .iter()
-        .map(|line| line.chars())
-        .fold(HashSet::<char>::new(), |set, chars| {
-            set.union(&chars.collect()).copied().collect()
+fn parse_groups<S: AsRef<str>>(lines: &[S]) -> anyhow::Result<Vec<Group>> {
+    let groups = records::paragraphs(lines)
+        .map(|(_, group)| {
+            group
+                .iter()
+                .map(|line| line.as_ref().parse())
+                .collect::<Result<Vec<_>, _>>()
+                .map(|people| Group { people })
         })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(groups)
+}
+
+/// Checks that `lines` parse as the answers of groups of people.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    parse_groups(lines)?;
+    Ok(())
 }
 
-fn problem2_parse_forms(lines: &[String]) -> Vec<HashSet<char>> {
-    let groups = lines.split(|line| line.is_empty());
-    groups.map(problem2_parse_form).collect()
+/// Solves both parts of the puzzle for the answers in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let groups = parse_groups(lines)?;
+    sum_answers(&groups, Group::anyone);
+    sum_answers(&groups, Group::everyone);
+    Ok(())
 }
 
-fn problem2_parse_form(group: &[String]) -> HashSet<char> {
-    let chars_by_line = group.iter().map(|line| line.chars());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 15] = [
+        "abc", "", "a", "b", "c", "", "ab", "ac", "", "a", "a", "a", "a", "", "b",
+    ];
+
+    fn set(s: &str) -> AnswerSet {
+        s.parse().unwrap()
+    }
 
-    let mut set = HashSet::<char>::new();
-    for (i, chars) in chars_by_line.enumerate() {
-        if i == 0 {
-            set = chars.collect();
-        } else {
-            set = set.intersection(&chars.collect()).copied().collect();
+    fn group(people: &[&str]) -> Group {
+        Group {
+            people: people.iter().map(|person| set(person)).collect(),
         }
     }
 
-    set
+    #[test]
+    fn test_answer_set() {
+        assert_eq!(set(""), AnswerSet::default());
+        assert_eq!(set("zaa").len(), 2);
+        assert!(set("abz").contains(25));
+        assert!(!set("abz").contains(2));
+        assert_eq!(set("abc").union(set("cd")), set("abcd"));
+        assert_eq!(set("abc").intersection(set("cd")), set("c"));
+        assert_eq!(set("abc").symmetric_difference(set("cd")), set("abd"));
+        assert_eq!(set("abcdefghijklmnopqrstuvwxyz"), AnswerSet::ALL);
+
+        assert!("abC".parse::<AnswerSet>().is_err());
+        assert!("a b".parse::<AnswerSet>().is_err());
+    }
+
+    #[test]
+    fn test_example() {
+        let groups = parse_groups(&EXAMPLE).unwrap();
+        let anyone = groups.iter().map(|g| g.anyone().len()).collect::<Vec<_>>();
+        let everyone = groups
+            .iter()
+            .map(|g| g.everyone().len())
+            .collect::<Vec<_>>();
+        assert_eq!(anyone, [3, 3, 3, 1, 1]);
+        assert_eq!(everyone, [3, 0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_group() {
+        let group = group(&["abc", "bcd", "cde"]);
+        assert_eq!(group.anyone(), set("abcde"));
+        assert_eq!(group.everyone(), set("c"));
+        assert_eq!(group.odd(), set("ace"));
+        assert_eq!(group.at_least(2), set("bcd"));
+        assert_eq!(group.at_least(0), AnswerSet::ALL);
+        assert_eq!(group.at_least(4), AnswerSet::default());
+    }
+
+    #[test]
+    fn test_empty_group() {
+        let group = group(&[]);
+        assert_eq!(group.anyone(), AnswerSet::default());
+        assert_eq!(group.everyone(), AnswerSet::ALL);
+        assert_eq!(group.odd(), AnswerSet::default());
+    }
 }
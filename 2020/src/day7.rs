@@ -1,40 +1,79 @@
-use anyhow::Result;
-use lazy_static::lazy_static;
-use regex::Regex;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::{HashMap, HashSet};
-use std::convert::TryInto;
-use std::fs::File;
-use std::io::{self, prelude::*};
 use std::path::Path;
 
+use crate::generate::check_size;
+use crate::input;
+use crate::parser::{
+    self, alt, literal, map, pair, preceded, recognize, separated, terminated, unsigned, word,
+    ParseError,
+};
+
+#[derive(Debug)]
+struct ParseBagRuleError {
+    line: String,
+    source: ParseError,
+}
+
+impl std::error::Error for ParseBagRuleError {}
+
+impl std::fmt::Display for ParseBagRuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to parse bag rule `{}`: {}.",
+            self.line, self.source
+        )
+    }
+}
+
+#[derive(Debug)]
+enum CountBagsError {
+    UndefinedBag { name: String },
+    Cycle { name: String },
+    Overflow,
+}
+
+impl std::error::Error for CountBagsError {}
+
+impl std::fmt::Display for CountBagsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CountBagsError::UndefinedBag { name } => {
+                write!(f, "There is no rule for {} bags.", name)
+            }
+            CountBagsError::Cycle { name } => {
+                write!(f, "{} bags end up inside themselves.", name)
+            }
+            CountBagsError::Overflow => write!(f, "There are too many bags to count."),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct BagRule<'a> {
     name: &'a str,
     possible_contents: Vec<(u32, &'a str)>,
 }
 
-pub fn problem1() -> anyhow::Result<()> {
+pub fn problem1() -> anyhow::Result<usize> {
     let path = Path::new("input_day7.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
-
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+    let lines = input::read_lines(path)?;
 
     let bag_rules = parse_bag_rules(&lines.iter().map(AsRef::as_ref).collect::<Vec<_>>())?;
-    let possible_direct_containers = compute_possible_direct_containers(&bag_rules)?;
+    count_possible_containers(&bag_rules, "shiny gold")
+}
 
-    let initial_color = "shiny gold";
-    let mut to_visit = vec![initial_color];
+/// The number of colours of bag which could, however deeply nested, hold a bag of `color`.
+fn count_possible_containers(bag_rules: &[BagRule], color: &str) -> anyhow::Result<usize> {
+    let possible_direct_containers = compute_possible_direct_containers(bag_rules)?;
+
+    let mut to_visit = vec![color];
     let mut visited_colors = HashSet::<&str>::new();
 
-    while !to_visit.is_empty() {
-        let active = to_visit.pop().unwrap();
+    while let Some(active) = to_visit.pop() {
         visited_colors.insert(active);
         let empty = HashSet::<&str>::new();
         let reachable = possible_direct_containers.get(active).unwrap_or(&empty);
@@ -45,85 +84,201 @@ pub fn problem1() -> anyhow::Result<()> {
             .collect::<Vec<_>>();
         to_visit.extend(&new_to_visit);
     }
-    visited_colors.remove(&initial_color);
-    println!("{}", visited_colors.len());
-    Ok(())
+    visited_colors.remove(color);
+    Ok(visited_colors.len())
 }
 
-pub fn problem2() -> anyhow::Result<()> {
+pub fn problem2() -> anyhow::Result<usize> {
     let path = Path::new("input_day7.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
-
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+    let lines = input::read_lines(path)?;
 
     let bag_rules = parse_bag_rules(&lines.iter().map(AsRef::as_ref).collect::<Vec<_>>())?;
+    Ok(count_bags_inside(&bag_rules, "shiny gold")?)
+}
+
+/// The number of bags, however deeply nested, inside a bag of `color`.
+///
+/// A depth-first search counts the bags inside each colour once, after counting those
+/// inside every colour it contains. Meeting a colour again while it is still being counted
+/// means it is inside itself, and then there is no total to find.
+fn count_bags_inside(bag_rules: &[BagRule], color: &str) -> Result<usize, CountBagsError> {
     let bag_rules_map = bag_rules
         .iter()
         .map(|br| (br.name, br))
         .collect::<HashMap<&str, &BagRule>>();
 
-    let initial_color = "shiny gold";
-    let mut to_visit = vec![(initial_color)];
-    let mut num_visited = 0;
-
-    while !to_visit.is_empty() {
-        let active_name = to_visit.pop().unwrap();
-        let active_bag_rule = bag_rules_map.get(active_name).unwrap();
-        num_visited += 1;
-        for new_to_visit in &active_bag_rule.possible_contents {
-            to_visit.extend(
-                &std::iter::repeat(new_to_visit.1)
-                    .take(new_to_visit.0.try_into().unwrap())
-                    .collect::<Vec<_>>(),
-            );
+    let mut bags_inside = HashMap::<&str, usize>::new();
+    let mut in_progress = HashSet::<&str>::new();
+    let mut to_visit = vec![(color, false)];
+
+    while let Some((active_name, contents_counted)) = to_visit.pop() {
+        let active_bag_rule =
+            bag_rules_map
+                .get(active_name)
+                .ok_or_else(|| CountBagsError::UndefinedBag {
+                    name: active_name.to_string(),
+                })?;
+
+        if contents_counted {
+            let mut total: usize = 0;
+            for &(count, name) in &active_bag_rule.possible_contents {
+                let bags = bags_inside[name]
+                    .checked_add(1)
+                    .and_then(|bags| bags.checked_mul(count as usize))
+                    .and_then(|bags| bags.checked_add(total));
+                total = bags.ok_or(CountBagsError::Overflow)?;
+            }
+            bags_inside.insert(active_name, total);
+            in_progress.remove(active_name);
+        } else if !bags_inside.contains_key(active_name) {
+            if !in_progress.insert(active_name) {
+                return Err(CountBagsError::Cycle {
+                    name: active_name.to_string(),
+                });
+            }
+            to_visit.push((active_name, true));
+            for &(_, name) in &active_bag_rule.possible_contents {
+                to_visit.push((name, false));
+            }
         }
     }
-    println!("{}", num_visited - 1);
-    Ok(())
+    Ok(bags_inside[color])
 }
 
-fn parse_bag_rules<'a>(lines: &[&'a str]) -> anyhow::Result<Vec<BagRule<'a>>> {
-    lines.iter().map(|line| parse_bag_rule(&line)).collect()
-}
+/// `size` random bag rules, including one for shiny gold bags.
+///
+/// Every bag is given a level, and only contains bags of lower levels, so the rules never
+/// form a cycle and the number of bags inside any one bag stays manageable.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    const ADJECTIVES: [&str; 18] = [
+        "bright", "clear", "dark", "dim", "dotted", "drab", "dull", "faded", "light", "mirrored",
+        "muted", "pale", "plaid", "posh", "shiny", "striped", "vibrant", "wavy",
+    ];
+    const COLORS: [&str; 33] = [
+        "aqua",
+        "beige",
+        "black",
+        "blue",
+        "bronze",
+        "brown",
+        "chartreuse",
+        "coral",
+        "crimson",
+        "cyan",
+        "fuchsia",
+        "gold",
+        "gray",
+        "green",
+        "indigo",
+        "lavender",
+        "lime",
+        "magenta",
+        "maroon",
+        "olive",
+        "orange",
+        "plum",
+        "purple",
+        "red",
+        "salmon",
+        "silver",
+        "tan",
+        "teal",
+        "tomato",
+        "turquoise",
+        "violet",
+        "white",
+        "yellow",
+    ];
+    const LEVELS: usize = 5;
+    check_size(size, 1..=ADJECTIVES.len() * COLORS.len())?;
 
-fn parse_bag_rule(line: &str) -> anyhow::Result<BagRule> {
-    #[rustfmt::skip]
-    lazy_static! {
-        static ref CONTAINER_BAG: Regex = Regex::new(r"^([a-z ]+) bags contain ").unwrap();
-        static ref CONTAINED_BAG_NONZERO: Regex = Regex::new(r"(\d+) ([a-z ]+) bags?[,.]").unwrap();
-        static ref CONTAINED_BAG_ZERO: Regex = #[allow(clippy::trivial_regex)] Regex::new(r"no other bags\.").unwrap();
-    }
-
-    // TODO: Handle errors
-    let container_name = CONTAINER_BAG
-        .captures(&line)
-        .unwrap()
-        .get(1)
-        .unwrap()
-        .as_str();
-
-    let contained_bags = CONTAINED_BAG_NONZERO
-        .captures_iter(&line)
-        .map(|captures| {
-            (
-                captures.get(1).unwrap().as_str().parse::<u32>().unwrap(),
-                captures.get(2).unwrap().as_str(),
-            )
+    let initial_color = "shiny gold".to_string();
+    let mut names = ADJECTIVES
+        .iter()
+        .flat_map(|adjective| {
+            COLORS
+                .iter()
+                .map(move |color| format!("{} {}", adjective, color))
         })
+        .filter(|name| *name != initial_color)
         .collect::<Vec<_>>();
+    names.shuffle(rng);
+    names.truncate(size - 1);
+    names.push(initial_color.clone());
 
-    // TODO: Validate the zero bag text matches
+    // Shiny gold bags go in the middle, so that they both contain and are contained by
+    // other bags.
+    let mut levels = vec![Vec::new(); LEVELS];
+    for name in &names {
+        let level = if *name == initial_color {
+            LEVELS / 2
+        } else {
+            rng.gen_range(0..LEVELS)
+        };
+        levels[level].push(name.as_str());
+    }
 
-    Ok(BagRule {
-        name: container_name,
-        possible_contents: contained_bags,
+    let mut rules = Vec::with_capacity(size);
+    for (level, level_names) in levels.iter().enumerate() {
+        let below = levels[..level].concat();
+        for name in level_names {
+            let num_contents = if below.is_empty() {
+                0
+            } else {
+                rng.gen_range(1..=4.min(below.len()))
+            };
+            let contents = below
+                .choose_multiple(rng, num_contents)
+                .map(|contained| {
+                    let count = rng.gen_range(1..=5);
+                    let bags = if count == 1 { "bag" } else { "bags" };
+                    format!("{} {} {}", count, contained, bags)
+                })
+                .collect::<Vec<_>>();
+            let contents = if contents.is_empty() {
+                "no other bags".to_string()
+            } else {
+                contents.join(", ")
+            };
+            rules.push(format!("{} bags contain {}.\n", name, contents));
+        }
+    }
+    rules.shuffle(rng);
+    Ok(rules.concat())
+}
+
+fn parse_bag_rules<'a>(lines: &[&'a str]) -> anyhow::Result<Vec<BagRule<'a>>> {
+    lines.iter().map(|line| parse_bag_rule(line)).collect()
+}
+
+fn parse_bag_rule(line: &str) -> anyhow::Result<BagRule<'_>> {
+    // Every colour is an adjective followed by a colour, such as `shiny gold`.
+    let color = || recognize(pair(word(), preceded(literal(" "), word())));
+    let contained_bag = pair(
+        terminated(unsigned(), literal(" ")),
+        terminated(color(), alt(literal(" bags"), literal(" bag"))),
+    );
+    let contents = alt(
+        map(literal("no other bags"), |_| Vec::new()),
+        separated(contained_bag, literal(", ")),
+    );
+    let rule = map(
+        pair(
+            terminated(color(), literal(" bags contain ")),
+            terminated(contents, literal(".")),
+        ),
+        |(name, possible_contents)| BagRule {
+            name,
+            possible_contents,
+        },
+    );
+
+    parser::parse(&rule, line).map_err(|source| {
+        ParseBagRuleError {
+            line: line.to_string(),
+            source,
+        }
+        .into()
     })
 }
 
@@ -137,7 +292,7 @@ fn compute_possible_direct_containers<'a>(
             match possible_direct_containers.get_mut(child_name) {
                 None => {
                     possible_direct_containers
-                        .insert(&child_name, vec![bag_rule.name].into_iter().collect());
+                        .insert(child_name, vec![bag_rule.name].into_iter().collect());
                 }
                 Some(set) => {
                     set.insert(bag_rule.name);
@@ -148,3 +303,153 @@ fn compute_possible_direct_containers<'a>(
 
     Ok(possible_direct_containers)
 }
+
+/// Checks that `lines` parse as bag rules.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    parse_bag_rules(lines)?;
+    Ok(())
+}
+
+/// Solves both parts of the puzzle for the bag rules in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let bag_rules = parse_bag_rules(lines)?;
+    let first = count_possible_containers(&bag_rules, "shiny gold");
+    let second = count_bags_inside(&bag_rules, "shiny gold");
+    first?;
+    second?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [&str; 7] = [
+        "shiny gold bags contain 2 dark red bags.",
+        "dark red bags contain 2 dark orange bags.",
+        "dark orange bags contain 2 dark yellow bags.",
+        "dark yellow bags contain 2 dark green bags.",
+        "dark green bags contain 2 dark blue bags.",
+        "dark blue bags contain 2 dark violet bags.",
+        "dark violet bags contain no other bags.",
+    ];
+
+    #[test]
+    fn test_count_bags_inside() {
+        let bag_rules = parse_bag_rules(&EXAMPLE).unwrap();
+        assert_eq!(count_bags_inside(&bag_rules, "shiny gold").unwrap(), 126);
+        assert_eq!(count_bags_inside(&bag_rules, "dark violet").unwrap(), 0);
+    }
+
+    /// Rules for `levels` levels of bags, each of which holds 10 bags of the next.
+    fn nested_rules(levels: u8) -> Vec<String> {
+        let name = |level: u8| format!("deep {}", (b'a' + level) as char);
+        (0..levels)
+            .map(|level| format!("{} bags contain 10 {} bags.", name(level), name(level + 1)))
+            .chain(std::iter::once(format!(
+                "{} bags contain no other bags.",
+                name(levels)
+            )))
+            .collect()
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        // Far too many bags to visit one by one.
+        let rules = nested_rules(15);
+        let bag_rules =
+            parse_bag_rules(&rules.iter().map(AsRef::as_ref).collect::<Vec<_>>()).unwrap();
+        assert_eq!(
+            count_bags_inside(&bag_rules, "deep a").unwrap(),
+            1_111_111_111_111_110
+        );
+
+        let rules = nested_rules(25);
+        let bag_rules =
+            parse_bag_rules(&rules.iter().map(AsRef::as_ref).collect::<Vec<_>>()).unwrap();
+        assert!(matches!(
+            count_bags_inside(&bag_rules, "deep a"),
+            Err(CountBagsError::Overflow)
+        ));
+    }
+
+    #[test]
+    fn test_shared_bags() {
+        // Each level holds one of both bags of the next, so there are 2^40 ways down to
+        // the bottom, far too many to follow one by one.
+        let name = |side: &str, level: u8| {
+            format!(
+                "{} {}{}",
+                side,
+                (b'a' + level / 26) as char,
+                (b'a' + level % 26) as char
+            )
+        };
+        let mut rules = Vec::new();
+        for level in 0..40 {
+            for side in ["left", "right"] {
+                rules.push(format!(
+                    "{} bags contain 1 {} bag, 1 {} bag.",
+                    name(side, level),
+                    name("left", level + 1),
+                    name("right", level + 1)
+                ));
+            }
+        }
+        for side in ["left", "right"] {
+            rules.push(format!("{} bags contain no other bags.", name(side, 40)));
+        }
+        let bag_rules =
+            parse_bag_rules(&rules.iter().map(AsRef::as_ref).collect::<Vec<_>>()).unwrap();
+        assert_eq!(
+            count_bags_inside(&bag_rules, "left aa").unwrap(),
+            (1 << 41) - 2
+        );
+    }
+
+    #[test]
+    fn test_cycles() {
+        let bag_rules = parse_bag_rules(&["shiny gold bags contain 1 shiny gold bag."]).unwrap();
+        assert_eq!(
+            count_bags_inside(&bag_rules, "shiny gold")
+                .unwrap_err()
+                .to_string(),
+            "shiny gold bags end up inside themselves."
+        );
+
+        let bag_rules = parse_bag_rules(&[
+            "shiny gold bags contain 2 dark red bags.",
+            "dark red bags contain 1 shiny gold bag.",
+        ])
+        .unwrap();
+        assert!(matches!(
+            count_bags_inside(&bag_rules, "shiny gold"),
+            Err(CountBagsError::Cycle { .. })
+        ));
+
+        // Even a rule for no bags at all closes a cycle.
+        let bag_rules = parse_bag_rules(&[
+            "shiny gold bags contain 2 dark red bags.",
+            "dark red bags contain 0 shiny gold bags, 3 dark blue bags.",
+            "dark blue bags contain no other bags.",
+        ])
+        .unwrap();
+        assert!(matches!(
+            count_bags_inside(&bag_rules, "shiny gold"),
+            Err(CountBagsError::Cycle { .. })
+        ));
+        // Bags outside the cycle can still be counted.
+        assert_eq!(count_bags_inside(&bag_rules, "dark blue").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_undefined_bag() {
+        let bag_rules = parse_bag_rules(&EXAMPLE[..6]).unwrap();
+        assert_eq!(
+            count_bags_inside(&bag_rules, "shiny gold")
+                .unwrap_err()
+                .to_string(),
+            "There is no rule for dark violet bags."
+        );
+    }
+}
@@ -1,18 +1,32 @@
-use anyhow::Result;
-use lazy_static::lazy_static;
-use regex::Regex;
+use rand::rngs::StdRng;
+use rand::Rng;
 use std::fs::File;
 use std::io::{self, prelude::*};
 use std::path::Path;
 
+use crate::generate::check_size;
+use crate::input;
+use crate::parser::{self, literal, map, one_of, pair, signed, terminated, ParseError};
+
+mod assembler;
+mod cfg;
+mod trace;
+
 #[derive(Debug)]
-struct ParseInstructionError;
+struct ParseInstructionError {
+    line: usize,
+    source: ParseError,
+}
 
 impl std::error::Error for ParseInstructionError {}
 
 impl std::fmt::Display for ParseInstructionError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Failed to parse instruction.")
+        write!(
+            f,
+            "Failed to parse instruction on line {}: {}.",
+            self.line, self.source
+        )
     }
 }
 
@@ -27,6 +41,20 @@ impl std::fmt::Display for ProcessorStateError {
     }
 }
 
+#[derive(Debug)]
+struct ProblemInvariantError;
+
+impl std::error::Error for ProblemInvariantError {}
+
+impl std::fmt::Display for ProblemInvariantError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "An invariant of the Advent of Code challenge seems to have been violated."
+        )
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 enum Instruction {
     Acc(isize),
@@ -34,6 +62,16 @@ enum Instruction {
     Nop(isize),
 }
 
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Instruction::Acc(value) => write!(f, "acc {:+}", value),
+            Instruction::Jmp(value) => write!(f, "jmp {:+}", value),
+            Instruction::Nop(value) => write!(f, "nop {:+}", value),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ProcessorState {
     visit_counts: Vec<u32>,
@@ -45,110 +83,231 @@ struct ProcessorState {
 enum TerminationMode {
     Normal,
     Deadlock,
+    /// Execution was cut short by a step limit before any instruction ran twice.
+    StepLimit,
 }
 
-pub fn problem1() -> anyhow::Result<()> {
-    let path = Path::new("input_day8.txt");
-    let display = path.display();
+/// The result of searching for a single `jmp`/`nop` flip which lets a program terminate.
+///
+/// `candidates` lists every address on the original execution path whose flip leads to
+/// termination, in execution order; `patched_address` is the first of them, so that a
+/// program with several possible repairs is always patched the same way.
+#[derive(Debug)]
+struct RepairAnalysis {
+    patched_address: usize,
+    candidates: Vec<usize>,
+}
+
+pub fn problem1() -> anyhow::Result<isize> {
+    accumulator_at_loop(&read_instructions(Path::new("input_day8.txt"))?)
+}
 
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
+pub fn problem2() -> anyhow::Result<isize> {
+    accumulator_after_repair(read_instructions(Path::new("input_day8.txt"))?)
+}
+
+/// The accumulator just before any instruction runs a second time.
+fn accumulator_at_loop(instructions: &[Instruction]) -> anyhow::Result<isize> {
+    let mut processor_state = ProcessorState {
+        visit_counts: vec![0; instructions.len()],
+        instruction_counter: 0,
+        accumulator: 0,
     };
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+    let termination_mode = tick_until_done_or_deadlocked(instructions, &mut processor_state)?;
+    if termination_mode != TerminationMode::Deadlock {
+        return Err(ProblemInvariantError.into());
+    }
+    Ok(processor_state.accumulator)
+}
 
-    let instructions = lines
-        .iter()
-        .map(|s| parse_instruction(s))
-        .collect::<Option<Vec<_>>>()
-        .ok_or(ParseInstructionError)?;
+/// The accumulator once the program terminates, after flipping the instruction which lets
+/// it.
+fn accumulator_after_repair(instructions: Vec<Instruction>) -> anyhow::Result<isize> {
+    let repair_analysis = analyze_repair(&instructions)?;
+    let mut patched_instructions = instructions;
+    patched_instructions[repair_analysis.patched_address] =
+        flip_instruction(patched_instructions[repair_analysis.patched_address])
+            .ok_or(ProblemInvariantError)?;
 
     let mut processor_state = ProcessorState {
-        visit_counts: vec![0; instructions.len()],
+        visit_counts: vec![0; patched_instructions.len()],
         instruction_counter: 0,
         accumulator: 0,
     };
 
-    let termination_mode = tick_until_done_or_deadlocked(&instructions, &mut processor_state)?;
-    assert_eq!(TerminationMode::Deadlock, termination_mode);
-    println!("{}", processor_state.accumulator);
+    let termination_mode =
+        tick_until_done_or_deadlocked(&patched_instructions, &mut processor_state)?;
+    if termination_mode != TerminationMode::Normal {
+        return Err(ProblemInvariantError.into());
+    }
+    Ok(processor_state.accumulator)
+}
 
+/// Assembles the source file at `path` and prints the resulting boot code.
+pub fn assemble(path: &Path) -> anyhow::Result<()> {
+    let source = input::read_source(path)?;
+    for instruction in assembler::assemble(&source)? {
+        println!("{}", instruction);
+    }
     Ok(())
 }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day8.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
+/// Prints the boot code at `path` as labelled, annotated source.
+pub fn disassemble(path: &Path) -> anyhow::Result<()> {
+    let instructions = read_instructions(path)?;
+    print!("{}", assembler::disassemble(&instructions));
+    Ok(())
+}
 
-    let lines = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?;
+/// Prints the control-flow graph of the boot code at `path` in DOT format.
+pub fn export_cfg(path: &Path) -> anyhow::Result<()> {
+    let instructions = read_instructions(path)?;
+    print!("{}", cfg::export_dot(&instructions));
+    Ok(())
+}
 
-    let instructions = lines
-        .iter()
-        .map(|s| parse_instruction(s))
-        .collect::<Option<Vec<_>>>()
-        .ok_or(ParseInstructionError)?;
+/// Prints the address which [`problem2`] patches to make the boot code at `path` terminate,
+/// and every other address whose flip would also do.
+pub fn repair(path: &Path) -> anyhow::Result<()> {
+    let instructions = read_instructions(path)?;
+    let repair_analysis = analyze_repair(&instructions)?;
+    print!("{}", render_repair(&instructions, &repair_analysis)?);
+    Ok(())
+}
 
-    let mut processor_state = ProcessorState {
-        visit_counts: vec![0; instructions.len()],
-        instruction_counter: 0,
-        accumulator: 0,
-    };
-    let mut found = false;
-
-    for i in 0..instructions.len() {
-        let mut mutated_instructions = instructions.to_vec();
-        if let Instruction::Jmp(x) = mutated_instructions[i] {
-            mutated_instructions[i] = Instruction::Nop(x);
-        } else if let Instruction::Nop(x) = mutated_instructions[i] {
-            mutated_instructions[i] = Instruction::Jmp(x);
+fn render_repair(
+    instructions: &[Instruction],
+    repair_analysis: &RepairAnalysis,
+) -> anyhow::Result<String> {
+    let mut output = String::new();
+    for &address in &repair_analysis.candidates {
+        let instruction = instructions[address];
+        let flipped = flip_instruction(instruction).ok_or(ProblemInvariantError)?;
+        let marker = if address == repair_analysis.patched_address {
+            "patched"
         } else {
+            "candidate"
+        };
+        output += &format!(
+            "{:>9} {:>5}: {} -> {}\n",
+            marker, address, instruction, flipped
+        );
+    }
+    Ok(output)
+}
+
+/// Runs the boot code at `path`, writing a JSON Lines trace of every executed instruction to
+/// `trace_path`, and prints a profile of the run.
+pub fn trace(path: &Path, trace_path: &Path, max_steps: Option<usize>) -> anyhow::Result<()> {
+    let instructions = read_instructions(path)?;
+    let mut output = io::BufWriter::new(File::create(trace_path)?);
+    let profile = trace::trace(&instructions, max_steps, &mut output)?;
+    output.flush()?;
+    print!("{}", profile);
+    Ok(())
+}
+
+/// A random program of `size` instructions which loops forever, but which terminates if
+/// exactly one of its instructions, and no other, is flipped between `jmp` and `nop`.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 3..=100_000)?;
+
+    loop {
+        // Jumps only go forwards, so this program always terminates.
+        let mut instructions = (0..size)
+            .map(|address| {
+                let max_offset = (size - address) as isize;
+                match rng.gen_range(0..10) {
+                    0..=4 => Instruction::Acc(rng.gen_range(-50..=50)),
+                    5..=7 => Instruction::Jmp(rng.gen_range(1..=max_offset.min(10))),
+                    _ => Instruction::Nop(rng.gen_range(-(address as isize)..=0)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut path = Vec::new();
+        let mut address = 0;
+        while address < size {
+            path.push(address);
+            address = successor(&instructions, address, instructions[address])
+                .ok_or(ProcessorStateError)?;
+        }
+        if path.len() < 2 {
             continue;
         }
 
-        processor_state = ProcessorState {
-            visit_counts: vec![0; instructions.len()],
-            instruction_counter: 0,
-            accumulator: 0,
-        };
-
-        let termination_mode =
-            tick_until_done_or_deadlocked(&mutated_instructions, &mut processor_state)?;
-        if termination_mode == TerminationMode::Normal {
-            found = true;
-            break;
+        // Corrupt an instruction on the path into a jump back to an earlier one, making the
+        // program loop, unless flipping anything else would also break the loop.
+        let corrupted = rng.gen_range(1..path.len());
+        let target = path[rng.gen_range(0..corrupted)];
+        let address = path[corrupted];
+        instructions[address] = Instruction::Jmp(target as isize - address as isize);
+        if matches!(analyze_repair(&instructions), Ok(analysis) if analysis.candidates == [address])
+        {
+            return Ok(instructions
+                .iter()
+                .map(|instruction| format!("{}\n", instruction))
+                .collect());
         }
     }
+}
+
+fn read_instructions(path: &Path) -> anyhow::Result<Vec<Instruction>> {
+    let lines = input::read_lines(path)?;
+    Ok(parse_instructions(&lines)?)
+}
 
-    assert!(found);
-    println!("{}", processor_state.accumulator);
+fn parse_instructions<S: AsRef<str>>(
+    lines: &[S],
+) -> Result<Vec<Instruction>, ParseInstructionError> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            parse_instruction(s.as_ref()).map_err(|source| ParseInstructionError {
+                line: i + 1,
+                source,
+            })
+        })
+        .collect()
+}
 
+/// Checks that `lines` parse as boot code.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    parse_instructions(lines)?;
     Ok(())
 }
 
-fn parse_instruction(line: &str) -> Option<Instruction> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"^(acc|jmp|nop) ([+-]\d+)$").unwrap();
-    }
+/// Solves both parts of the puzzle for the boot code in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let instructions = parse_instructions(lines)?;
+    let first = accumulator_at_loop(&instructions);
+    let second = accumulator_after_repair(instructions);
+    first?;
+    second?;
+    Ok(())
+}
 
-    let captures = RE.captures(line)?;
-    let instruction = match captures.get(1)?.as_str() {
-        "acc" => Instruction::Acc(captures.get(2)?.as_str().parse::<isize>().ok()?),
-        "jmp" => Instruction::Jmp(captures.get(2)?.as_str().parse::<isize>().ok()?),
-        "nop" => Instruction::Nop(captures.get(2)?.as_str().parse::<isize>().ok()?),
-        _ => return None,
-    };
+/// Checks that `source` assembles into boot code.
+pub(crate) fn check_source(source: &str) -> anyhow::Result<()> {
+    assembler::assemble(source)?;
+    Ok(())
+}
 
-    Some(instruction)
+fn parse_instruction(line: &str) -> Result<Instruction, ParseError> {
+    let instruction = map(
+        pair(
+            terminated(one_of(&["acc", "jmp", "nop"]), literal(" ")),
+            signed(),
+        ),
+        |(mnemonic, operand)| match mnemonic {
+            "acc" => Instruction::Acc(operand),
+            "jmp" => Instruction::Jmp(operand),
+            _ => Instruction::Nop(operand),
+        },
+    );
+    parser::parse(&instruction, line)
 }
 
 fn checked_unsigned_signed_add(x: usize, y: isize) -> Option<usize> {
@@ -161,7 +320,7 @@ fn checked_unsigned_signed_add(x: usize, y: isize) -> Option<usize> {
 
 fn tick(instructions: &[Instruction], processor_state: &mut ProcessorState) -> anyhow::Result<()> {
     processor_state.visit_counts[processor_state.instruction_counter] += 1;
-    match &instructions[(processor_state.instruction_counter)] {
+    match &instructions[processor_state.instruction_counter] {
         Instruction::Acc(value) => {
             processor_state.instruction_counter = processor_state
                 .instruction_counter
@@ -174,7 +333,7 @@ fn tick(instructions: &[Instruction], processor_state: &mut ProcessorState) -> a
         }
         Instruction::Jmp(value) => {
             processor_state.instruction_counter =
-                checked_unsigned_signed_add(processor_state.instruction_counter, *value)
+                jump_target(instructions, processor_state.instruction_counter, *value)
                     .ok_or(ProcessorStateError)?;
         }
         Instruction::Nop(_) => {
@@ -195,7 +354,7 @@ fn tick_until_done_or_deadlocked(
     while processor_state.instruction_counter != instructions.len()
         && processor_state.visit_counts[processor_state.instruction_counter] != 1
     {
-        tick(&instructions, processor_state)?;
+        tick(instructions, processor_state)?;
     }
 
     if processor_state.instruction_counter == instructions.len() {
@@ -206,3 +365,204 @@ fn tick_until_done_or_deadlocked(
         panic!("tick_until_done_or_deadlocked() entered an illegal state.");
     }
 }
+
+/// Swaps a `jmp` for a `nop` and vice versa, returning `None` for instructions which cannot
+/// be flipped.
+fn flip_instruction(instruction: Instruction) -> Option<Instruction> {
+    match instruction {
+        Instruction::Acc(_) => None,
+        Instruction::Jmp(x) => Some(Instruction::Nop(x)),
+        Instruction::Nop(x) => Some(Instruction::Jmp(x)),
+    }
+}
+
+/// Computes the address `offset` instructions away from `address`, or `None` if it lies
+/// anywhere other than within the program or just past its end.
+fn jump_target(instructions: &[Instruction], address: usize, offset: isize) -> Option<usize> {
+    let target = checked_unsigned_signed_add(address, offset)?;
+    if target <= instructions.len() {
+        Some(target)
+    } else {
+        None
+    }
+}
+
+/// Computes the address executed after `address`, or `None` if control would leave the
+/// program anywhere other than the address just past its end.
+fn successor(
+    instructions: &[Instruction],
+    address: usize,
+    instruction: Instruction,
+) -> Option<usize> {
+    match instruction {
+        Instruction::Acc(_) | Instruction::Nop(_) => jump_target(instructions, address, 1),
+        Instruction::Jmp(value) => jump_target(instructions, address, value),
+    }
+}
+
+/// Marks every address (including the terminal address `instructions.len()`) from which the
+/// unmodified program runs to normal termination.
+fn compute_terminating_addresses(instructions: &[Instruction]) -> Vec<bool> {
+    let mut predecessors = vec![Vec::new(); instructions.len() + 1];
+    for (address, &instruction) in instructions.iter().enumerate() {
+        if let Some(next) = successor(instructions, address, instruction) {
+            predecessors[next].push(address);
+        }
+    }
+
+    let mut terminating = vec![false; instructions.len() + 1];
+    terminating[instructions.len()] = true;
+    let mut to_visit = vec![instructions.len()];
+    while let Some(address) = to_visit.pop() {
+        for &predecessor in &predecessors[address] {
+            if !terminating[predecessor] {
+                terminating[predecessor] = true;
+                to_visit.push(predecessor);
+            }
+        }
+    }
+
+    terminating
+}
+
+/// Finds the `jmp`/`nop` flips which make the program terminate, in time linear in the
+/// length of the program.
+///
+/// Only instructions on the original execution path can influence the outcome, and since
+/// that path never terminates, none of its instructions can be reached again from an
+/// address which does. It therefore suffices to check whether the flipped successor of
+/// each instruction on the path can reach termination in the unmodified program.
+fn analyze_repair(instructions: &[Instruction]) -> anyhow::Result<RepairAnalysis> {
+    let terminating = compute_terminating_addresses(instructions);
+
+    let mut visited = vec![false; instructions.len()];
+    let mut candidates = Vec::new();
+    let mut address = 0;
+    while address < instructions.len() && !visited[address] {
+        visited[address] = true;
+        let instruction = instructions[address];
+
+        if let Some(flipped) = flip_instruction(instruction) {
+            if let Some(next) = successor(instructions, address, flipped) {
+                if terminating[next] {
+                    candidates.push(address);
+                }
+            }
+        }
+
+        address = successor(instructions, address, instruction).ok_or(ProcessorStateError)?;
+    }
+
+    let patched_address = *candidates.first().ok_or(ProblemInvariantError)?;
+    Ok(RepairAnalysis {
+        patched_address,
+        candidates,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::differential::{self, assert_agree};
+
+    fn run(instructions: &[Instruction]) -> anyhow::Result<TerminationMode> {
+        let mut processor_state = ProcessorState {
+            visit_counts: vec![0; instructions.len()],
+            instruction_counter: 0,
+            accumulator: 0,
+        };
+        tick_until_done_or_deadlocked(instructions, &mut processor_state)
+    }
+
+    /// The addresses whose flip makes a looping program terminate, found by flipping each
+    /// instruction in a copy of the program and running it.
+    fn brute_force_repairs(instructions: &[Instruction]) -> Vec<usize> {
+        (0..instructions.len())
+            .filter(|&address| match flip_instruction(instructions[address]) {
+                Some(flipped) => {
+                    let mut patched = instructions.to_vec();
+                    patched[address] = flipped;
+                    matches!(run(&patched), Ok(TerminationMode::Normal))
+                }
+                None => false,
+            })
+            .collect()
+    }
+
+    fn random_program(rng: &mut StdRng) -> Vec<Instruction> {
+        let size = rng.gen_range(1..=12);
+        (0..size)
+            .map(|_| {
+                let value = rng.gen_range(-(size as isize)..=size as isize);
+                match rng.gen_range(0..3) {
+                    0 => Instruction::Acc(value),
+                    1 => Instruction::Jmp(value),
+                    _ => Instruction::Nop(value),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_repairs_against_brute_force() {
+        // Repairs are only defined for programs which loop, and any other program agrees
+        // trivially.
+        let loops = |instructions: &[Instruction]| {
+            matches!(run(instructions), Ok(TerminationMode::Deadlock))
+        };
+
+        for case in 0..differential::CASES {
+            let mut rng = differential::rng(case);
+            let instructions = if case % 2 == 0 {
+                random_program(&mut rng)
+            } else {
+                let size = rng.gen_range(3..=20);
+                generate(size, &mut rng)
+                    .unwrap()
+                    .lines()
+                    .map(|line| parse_instruction(line).unwrap())
+                    .collect()
+            };
+
+            assert_agree(
+                &instructions,
+                |instructions| {
+                    if loops(instructions) {
+                        brute_force_repairs(instructions)
+                    } else {
+                        Vec::new()
+                    }
+                },
+                |instructions| {
+                    if loops(instructions) {
+                        let mut candidates = analyze_repair(instructions)
+                            .map(|analysis| analysis.candidates)
+                            .unwrap_or_default();
+                        candidates.sort_unstable();
+                        candidates
+                    } else {
+                        Vec::new()
+                    }
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_repair() {
+        // Flipping either the `nop` or the `jmp` skips straight to the last instruction.
+        let instructions = [
+            Instruction::Nop(2),
+            Instruction::Jmp(0),
+            Instruction::Acc(1),
+        ];
+        let repair_analysis = analyze_repair(&instructions).unwrap();
+        assert_eq!(repair_analysis.patched_address, 0);
+        assert_eq!(
+            render_repair(&instructions, &repair_analysis).unwrap(),
+            "  patched     0: nop +2 -> jmp +2\ncandidate     1: jmp +0 -> nop +0\n"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! A text format for boot code with labels and comments.
+//!
+//! Each line holds an optional `label:`, an optional instruction and an optional `; comment`.
+//! The operand of an instruction is either a signed offset (`jmp -4`) or, for `jmp` and `nop`,
+//! the name of a label (`jmp loop`), which is resolved to the offset of that label. A label on
+//! a line without an instruction refers to the next instruction, or to the address just past
+//! the end of the program if there is none.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use super::{jump_target, Instruction};
+
+#[derive(Debug)]
+pub struct AssemblyError {
+    line: usize,
+    message: String,
+}
+
+impl std::error::Error for AssemblyError {}
+
+impl std::fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug)]
+enum Operand<'a> {
+    Offset(isize),
+    Label(&'a str),
+}
+
+#[derive(Debug)]
+struct SourceLine<'a> {
+    line: usize,
+    label: Option<&'a str>,
+    instruction: Option<(&'a str, Operand<'a>)>,
+}
+
+fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+fn parse_source_line(line: usize, text: &str) -> Result<SourceLine<'_>, AssemblyError> {
+    let error = |message: String| AssemblyError { line, message };
+
+    let code = match text.find(';') {
+        Some(i) => &text[..i],
+        None => text,
+    };
+
+    let (label, rest) = match code.find(':') {
+        Some(i) => {
+            let label = code[..i].trim();
+            if !is_label(label) {
+                return Err(error(format!("invalid label `{}`", label)));
+            }
+            (Some(label), &code[i + 1..])
+        }
+        None => (None, code),
+    };
+
+    let tokens = rest.split_whitespace().collect::<Vec<_>>();
+    let instruction = match tokens.as_slice() {
+        [] => None,
+        [mnemonic, operand] => {
+            if !matches!(*mnemonic, "acc" | "jmp" | "nop") {
+                return Err(error(format!("unknown mnemonic `{}`", mnemonic)));
+            }
+            let operand = if let Ok(offset) = operand.parse::<isize>() {
+                Operand::Offset(offset)
+            } else if *mnemonic != "acc" && is_label(operand) {
+                Operand::Label(operand)
+            } else {
+                return Err(error(format!("invalid operand `{}`", operand)));
+            };
+            Some((*mnemonic, operand))
+        }
+        _ => return Err(error("expected `mnemonic operand`".to_string())),
+    };
+
+    Ok(SourceLine {
+        line,
+        label,
+        instruction,
+    })
+}
+
+/// Assembles source text into a program, resolving labels to relative offsets.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AssemblyError> {
+    let source_lines = source
+        .lines()
+        .enumerate()
+        .map(|(i, text)| parse_source_line(i + 1, text))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut labels = HashMap::new();
+    let mut address = 0;
+    for source_line in &source_lines {
+        if let Some(label) = source_line.label {
+            if labels.insert(label, address).is_some() {
+                return Err(AssemblyError {
+                    line: source_line.line,
+                    message: format!("duplicate label `{}`", label),
+                });
+            }
+        }
+        if source_line.instruction.is_some() {
+            address += 1;
+        }
+    }
+
+    let mut instructions = Vec::with_capacity(address);
+    for source_line in &source_lines {
+        let (mnemonic, operand) = match &source_line.instruction {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+
+        let value = match operand {
+            Operand::Offset(offset) => *offset,
+            Operand::Label(label) => {
+                let target = *labels.get(label).ok_or_else(|| AssemblyError {
+                    line: source_line.line,
+                    message: format!("undefined label `{}`", label),
+                })?;
+                target as isize - instructions.len() as isize
+            }
+        };
+
+        instructions.push(match *mnemonic {
+            "acc" => Instruction::Acc(value),
+            "jmp" => Instruction::Jmp(value),
+            _ => Instruction::Nop(value),
+        });
+    }
+
+    Ok(instructions)
+}
+
+fn label_name(address: usize) -> String {
+    format!("L{:04}", address)
+}
+
+/// Produces source text for a program which reassembles to the same program.
+///
+/// Every in-range `jmp`/`nop` target receives a label, and each instruction is annotated with
+/// its address and, for `jmp`/`nop`, the address it resolves to.
+pub fn disassemble(instructions: &[Instruction]) -> String {
+    let targets = instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(address, &instruction)| match instruction {
+            Instruction::Acc(_) => None,
+            Instruction::Jmp(value) | Instruction::Nop(value) => {
+                jump_target(instructions, address, value)
+            }
+        })
+        .collect::<HashSet<_>>();
+
+    let mut output = String::new();
+    for (address, &instruction) in instructions.iter().enumerate() {
+        let label = if targets.contains(&address) {
+            format!("{}:", label_name(address))
+        } else {
+            String::new()
+        };
+
+        let (mnemonic, value) = match instruction {
+            Instruction::Acc(value) => ("acc", value),
+            Instruction::Jmp(value) => ("jmp", value),
+            Instruction::Nop(value) => ("nop", value),
+        };
+
+        let (operand, comment) = match instruction {
+            Instruction::Acc(_) => (format!("{:+}", value), format!("{:04}", address)),
+            Instruction::Jmp(_) | Instruction::Nop(_) => {
+                match jump_target(instructions, address, value) {
+                    Some(target) => (
+                        label_name(target),
+                        format!("{:04} -> {:04}", address, target),
+                    ),
+                    None => (
+                        format!("{:+}", value),
+                        format!("{:04} -> out of range", address),
+                    ),
+                }
+            }
+        };
+
+        writeln!(
+            output,
+            "{:<7}{} {:<8}; {}",
+            label, mnemonic, operand, comment
+        )
+        .unwrap();
+    }
+
+    if targets.contains(&instructions.len()) {
+        writeln!(output, "{}:", label_name(instructions.len())).unwrap();
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    /// The example program from the puzzle.
+    const EXAMPLE: [Instruction; 9] = [
+        Instruction::Nop(0),
+        Instruction::Acc(1),
+        Instruction::Jmp(4),
+        Instruction::Acc(3),
+        Instruction::Jmp(-3),
+        Instruction::Acc(-99),
+        Instruction::Acc(1),
+        Instruction::Jmp(-4),
+        Instruction::Acc(6),
+    ];
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(assemble(&disassemble(&EXAMPLE)).unwrap(), EXAMPLE);
+
+        // Jumps past either end of the program, and to the address just past its end.
+        let out_of_range = [
+            Instruction::Jmp(-5),
+            Instruction::Nop(10),
+            Instruction::Jmp(1),
+        ];
+        assert_eq!(assemble(&disassemble(&out_of_range)).unwrap(), out_of_range);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let size = rng.gen_range(0..=20);
+            let program = (0..size)
+                .map(|_| {
+                    let value = rng.gen_range(-25..=25);
+                    match rng.gen_range(0..3) {
+                        0 => Instruction::Acc(value),
+                        1 => Instruction::Jmp(value),
+                        _ => Instruction::Nop(value),
+                    }
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(assemble(&disassemble(&program)).unwrap(), program);
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let program = [
+            Instruction::Acc(3),
+            Instruction::Jmp(-1),
+            Instruction::Nop(1),
+        ];
+        assert_eq!(
+            disassemble(&program),
+            "L0000: acc +3      ; 0000\n       \
+             jmp L0000   ; 0001 -> 0000\n       \
+             nop L0003   ; 0002 -> 0003\n\
+             L0003:\n"
+        );
+    }
+
+    #[test]
+    fn test_assemble_labels() {
+        let source = "start: nop end ; skip over nothing\n\
+                      loop:\n\
+                      acc -2\n\
+                      jmp loop\n\
+                      end:\n";
+        assert_eq!(
+            assemble(source).unwrap(),
+            vec![
+                Instruction::Nop(3),
+                Instruction::Acc(-2),
+                Instruction::Jmp(-1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assembly_errors() {
+        let error = |source: &str| assemble(source).unwrap_err().to_string();
+        assert_eq!(error("nop +0\nmul +2"), "line 2: unknown mnemonic `mul`");
+        assert_eq!(error("acc start"), "line 1: invalid operand `start`");
+        assert_eq!(error("jmp nowhere"), "line 1: undefined label `nowhere`");
+        assert_eq!(error("a: nop +0\na: nop +0"), "line 2: duplicate label `a`");
+        assert_eq!(error("1a: nop +0"), "line 1: invalid label `1a`");
+        assert_eq!(error("nop"), "line 1: expected `mnemonic operand`");
+    }
+}
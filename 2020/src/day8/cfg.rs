@@ -0,0 +1,148 @@
+//! Export of a program's control-flow graph in Graphviz DOT format.
+
+use std::fmt::Write;
+
+use super::{successor, Instruction};
+
+/// Marks every address from which execution eventually returns to the same address.
+///
+/// Each instruction has at most one successor, so every walk either leaves the program or
+/// runs into a single cycle; each address is walked over exactly once.
+fn compute_loop_addresses(instructions: &[Instruction]) -> Vec<bool> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        OnPath,
+        Done,
+    }
+
+    let mut states = vec![State::Unvisited; instructions.len()];
+    let mut in_loop = vec![false; instructions.len()];
+    for start in 0..instructions.len() {
+        let mut path = Vec::new();
+        let mut address = Some(start);
+        while let Some(a) = address.filter(|&a| a < instructions.len()) {
+            if states[a] != State::Unvisited {
+                break;
+            }
+            states[a] = State::OnPath;
+            path.push(a);
+            address = successor(instructions, a, instructions[a]);
+        }
+
+        if let Some(a) = address.filter(|&a| a < instructions.len() && states[a] == State::OnPath) {
+            let cycle_start = path.iter().position(|&p| p == a).unwrap();
+            for &p in &path[cycle_start..] {
+                in_loop[p] = true;
+            }
+        }
+
+        for p in path {
+            states[p] = State::Done;
+        }
+    }
+
+    in_loop
+}
+
+/// Marks every address executed when the program starts at address 0.
+fn compute_reachable_addresses(instructions: &[Instruction]) -> Vec<bool> {
+    let mut reachable = vec![false; instructions.len()];
+    let mut address = Some(0);
+    while let Some(a) = address.filter(|&a| a < instructions.len() && !reachable[a]) {
+        reachable[a] = true;
+        address = successor(instructions, a, instructions[a]);
+    }
+    reachable
+}
+
+/// Renders the control-flow graph of a program as a DOT digraph.
+///
+/// Instructions which are part of a loop are drawn in red, and instructions which are never
+/// executed from address 0 are drawn greyed out and dashed. Control flow leaving the program
+/// at its end is drawn as an edge to an `end` node, and jumps anywhere else outside the
+/// program as an edge to an `out_of_range` node.
+pub fn export_dot(instructions: &[Instruction]) -> String {
+    let in_loop = compute_loop_addresses(instructions);
+    let reachable = compute_reachable_addresses(instructions);
+
+    let mut output = String::new();
+    writeln!(output, "digraph boot_code {{").unwrap();
+    writeln!(output, "    node [shape=box, fontname=monospace];").unwrap();
+    writeln!(output, "    end [shape=doublecircle];").unwrap();
+
+    for (address, &instruction) in instructions.iter().enumerate() {
+        let mut attributes = vec![format!("label=\"{:04}: {}\"", address, instruction)];
+        if in_loop[address] {
+            attributes.push("color=red".to_string());
+            attributes.push("fontcolor=red".to_string());
+        }
+        if !reachable[address] {
+            attributes.push("style=dashed".to_string());
+            attributes.push("fontcolor=grey".to_string());
+        }
+        writeln!(output, "    n{} [{}];", address, attributes.join(", ")).unwrap();
+    }
+
+    for (address, &instruction) in instructions.iter().enumerate() {
+        match successor(instructions, address, instruction) {
+            Some(next) if next == instructions.len() => {
+                writeln!(output, "    n{} -> end;", address).unwrap()
+            }
+            Some(next) if in_loop[address] && in_loop[next] => {
+                writeln!(output, "    n{} -> n{} [color=red];", address, next).unwrap()
+            }
+            Some(next) => writeln!(output, "    n{} -> n{};", address, next).unwrap(),
+            None => writeln!(output, "    n{} -> out_of_range;", address).unwrap(),
+        }
+    }
+
+    if (0..instructions.len()).any(|a| successor(instructions, a, instructions[a]).is_none()) {
+        writeln!(output, "    out_of_range [shape=octagon, color=orange];").unwrap();
+    }
+
+    writeln!(output, "}}").unwrap();
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_dot() {
+        // Address 0 falls through into a loop between 1 and 2, and the jump at 3, which is
+        // never executed, leaves the program.
+        let program = [
+            Instruction::Nop(0),
+            Instruction::Acc(1),
+            Instruction::Jmp(-1),
+            Instruction::Jmp(5),
+        ];
+        assert_eq!(
+            export_dot(&program),
+            "digraph boot_code {\n    \
+             node [shape=box, fontname=monospace];\n    \
+             end [shape=doublecircle];\n    \
+             n0 [label=\"0000: nop +0\"];\n    \
+             n1 [label=\"0001: acc +1\", color=red, fontcolor=red];\n    \
+             n2 [label=\"0002: jmp -1\", color=red, fontcolor=red];\n    \
+             n3 [label=\"0003: jmp +5\", style=dashed, fontcolor=grey];\n    \
+             n0 -> n1;\n    \
+             n1 -> n2 [color=red];\n    \
+             n2 -> n1 [color=red];\n    \
+             n3 -> out_of_range;\n    \
+             out_of_range [shape=octagon, color=orange];\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_export_dot_terminating() {
+        let program = [Instruction::Acc(1), Instruction::Jmp(1)];
+        let dot = export_dot(&program);
+        assert!(dot.contains("    n0 -> n1;\n    n1 -> end;\n"));
+        assert!(!dot.contains("red"));
+        assert!(!dot.contains("out_of_range"));
+    }
+}
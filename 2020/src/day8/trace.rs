@@ -0,0 +1,231 @@
+//! Instruction-level tracing and profiling of boot code execution.
+
+use std::io::Write;
+
+use super::{tick, Instruction, ProcessorState, ProcessorStateError, TerminationMode};
+
+/// How many of the most frequently executed instructions a profile reports.
+const HOT_INSTRUCTION_COUNT: usize = 10;
+
+/// A summary of a traced run.
+#[derive(Debug)]
+pub struct Profile {
+    termination_mode: TerminationMode,
+    /// The number of instructions executed before an instruction was about to run twice.
+    steps_until_deadlock: Option<usize>,
+    /// The first instruction which was about to run twice.
+    loop_entry: Option<usize>,
+    /// The number of instructions executed in each iteration of the loop.
+    loop_length: Option<usize>,
+    total_steps: usize,
+    accumulator: isize,
+    hot_instructions: Vec<(usize, Instruction, u32)>,
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "termination:      {:?}", self.termination_mode)?;
+        writeln!(f, "steps executed:   {}", self.total_steps)?;
+        writeln!(f, "accumulator:      {}", self.accumulator)?;
+        if let (Some(steps), Some(entry), Some(length)) =
+            (self.steps_until_deadlock, self.loop_entry, self.loop_length)
+        {
+            writeln!(f, "deadlock after:   {} steps", steps)?;
+            writeln!(f, "loop entry:       {:04}", entry)?;
+            writeln!(f, "loop length:      {} instructions", length)?;
+        }
+        writeln!(f, "hot instructions:")?;
+        for (address, instruction, count) in &self.hot_instructions {
+            writeln!(
+                f,
+                "    {:04}: {:<10} {:>8} visits",
+                address,
+                instruction.to_string(),
+                count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs a program from address 0, writing one JSON object per executed instruction to
+/// `output`, and returns a profile of the run.
+///
+/// Without `max_steps` execution stops as soon as the program terminates or an instruction
+/// is about to run for the second time. With it, execution carries on around the loop until
+/// the program terminates or `max_steps` instructions have run, which makes the visit counts
+/// of instructions inside the loop meaningful.
+pub fn trace<W: Write>(
+    instructions: &[Instruction],
+    max_steps: Option<usize>,
+    output: &mut W,
+) -> anyhow::Result<Profile> {
+    let mut processor_state = ProcessorState {
+        visit_counts: vec![0; instructions.len()],
+        instruction_counter: 0,
+        accumulator: 0,
+    };
+    let mut first_visits = vec![None; instructions.len()];
+    let mut steps_until_deadlock = None;
+    let mut loop_entry = None;
+    let mut loop_length = None;
+    let mut step = 0;
+
+    let termination_mode = loop {
+        let address = processor_state.instruction_counter;
+        if address == instructions.len() {
+            break TerminationMode::Normal;
+        }
+        if address > instructions.len() {
+            return Err(ProcessorStateError.into());
+        }
+
+        if let Some(first_visit) = first_visits[address] {
+            if loop_entry.is_none() {
+                steps_until_deadlock = Some(step);
+                loop_entry = Some(address);
+                loop_length = Some(step - first_visit);
+            }
+            if max_steps.is_none() {
+                break TerminationMode::Deadlock;
+            }
+        } else {
+            first_visits[address] = Some(step);
+        }
+        if matches!(max_steps, Some(max_steps) if step >= max_steps) {
+            if loop_entry.is_some() {
+                break TerminationMode::Deadlock;
+            } else {
+                break TerminationMode::StepLimit;
+            }
+        }
+
+        let accumulator_before = processor_state.accumulator;
+        tick(instructions, &mut processor_state)?;
+        writeln!(
+            output,
+            "{{\"step\":{},\"address\":{},\"instruction\":\"{}\",\"accumulator_before\":{},\"accumulator_after\":{}}}",
+            step, address, instructions[address], accumulator_before, processor_state.accumulator
+        )?;
+        step += 1;
+    };
+
+    let mut hot_instructions = processor_state
+        .visit_counts
+        .iter()
+        .enumerate()
+        .filter(|(_, &count)| count > 0)
+        .map(|(address, &count)| (address, instructions[address], count))
+        .collect::<Vec<_>>();
+    hot_instructions.sort_by_key(|&(address, _, count)| (std::cmp::Reverse(count), address));
+    hot_instructions.truncate(HOT_INSTRUCTION_COUNT);
+
+    Ok(Profile {
+        termination_mode,
+        steps_until_deadlock,
+        loop_entry,
+        loop_length,
+        total_steps: step,
+        accumulator: processor_state.accumulator,
+        hot_instructions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example program from the puzzle, which loops back to address 1 after 7 steps.
+    const EXAMPLE: [Instruction; 9] = [
+        Instruction::Nop(0),
+        Instruction::Acc(1),
+        Instruction::Jmp(4),
+        Instruction::Acc(3),
+        Instruction::Jmp(-3),
+        Instruction::Acc(-99),
+        Instruction::Acc(1),
+        Instruction::Jmp(-4),
+        Instruction::Acc(6),
+    ];
+
+    fn trace_lines(
+        instructions: &[Instruction],
+        max_steps: Option<usize>,
+    ) -> (Profile, Vec<String>) {
+        let mut output = Vec::new();
+        let profile = trace(instructions, max_steps, &mut output).unwrap();
+        let lines = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        (profile, lines)
+    }
+
+    #[test]
+    fn test_trace_output() {
+        let (_, lines) = trace_lines(&EXAMPLE, None);
+        let addresses = lines
+            .iter()
+            .map(|line| line.split(",\"address\":").nth(1).unwrap())
+            .map(|rest| rest.split(',').next().unwrap().parse().unwrap())
+            .collect::<Vec<usize>>();
+        assert_eq!(addresses, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(
+            lines[1],
+            "{\"step\":1,\"address\":1,\"instruction\":\"acc +1\",\"accumulator_before\":0,\"accumulator_after\":1}"
+        );
+        assert_eq!(
+            lines[5],
+            "{\"step\":5,\"address\":3,\"instruction\":\"acc +3\",\"accumulator_before\":2,\"accumulator_after\":5}"
+        );
+    }
+
+    #[test]
+    fn test_loop_detection() {
+        let (profile, _) = trace_lines(&EXAMPLE, None);
+        assert_eq!(profile.termination_mode, TerminationMode::Deadlock);
+        assert_eq!(profile.steps_until_deadlock, Some(7));
+        assert_eq!(profile.loop_entry, Some(1));
+        assert_eq!(profile.loop_length, Some(6));
+        assert_eq!(profile.total_steps, 7);
+        assert_eq!(profile.accumulator, 5);
+
+        // Carrying on around the loop: two more iterations of 5 and one more `acc +1`.
+        let (profile, lines) = trace_lines(&EXAMPLE, Some(20));
+        assert_eq!(profile.termination_mode, TerminationMode::Deadlock);
+        assert_eq!(profile.steps_until_deadlock, Some(7));
+        assert_eq!(profile.loop_entry, Some(1));
+        assert_eq!(profile.loop_length, Some(6));
+        assert_eq!(profile.total_steps, 20);
+        assert_eq!(lines.len(), 20);
+        assert_eq!(profile.accumulator, 16);
+        assert_eq!(profile.hot_instructions[0], (1, Instruction::Acc(1), 4));
+    }
+
+    #[test]
+    fn test_termination_without_loop() {
+        let program = [
+            Instruction::Acc(2),
+            Instruction::Jmp(2),
+            Instruction::Acc(7),
+        ];
+        let (profile, lines) = trace_lines(&program, None);
+        assert_eq!(profile.termination_mode, TerminationMode::Normal);
+        assert_eq!(profile.loop_entry, None);
+        assert_eq!(profile.total_steps, 2);
+        assert_eq!(profile.accumulator, 2);
+        assert_eq!(lines.len(), 2);
+
+        let program = [Instruction::Acc(1); 3];
+        let (profile, _) = trace_lines(&program, Some(2));
+        assert_eq!(profile.termination_mode, TerminationMode::StepLimit);
+        assert_eq!(profile.total_steps, 2);
+    }
+
+    #[test]
+    fn test_jump_out_of_range() {
+        let program = [Instruction::Nop(0), Instruction::Jmp(5)];
+        assert!(trace(&program, None, &mut Vec::new()).is_err());
+    }
+}
@@ -1,117 +1,366 @@
-use anyhow::Result;
-use itertools::Itertools;
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{self, prelude::*};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::collections::HashMap;
 use std::path::Path;
 
+use crate::generate::check_size;
+use crate::{input, records};
+
+const PREAMBLE_SIZE: usize = 25;
+
 #[derive(Debug)]
-struct ProblemInvariantError;
+enum XmasError {
+    NoInvalidNumber { preamble_size: usize },
+    NoWeakness { invalid: u64 },
+}
 
-impl std::error::Error for ProblemInvariantError {}
+impl std::error::Error for XmasError {}
 
-impl std::fmt::Display for ProblemInvariantError {
+impl std::fmt::Display for XmasError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "An invariant of the Advent of Code challenge seems to have been violated."
-        )
-    }
-}
-
-pub fn problem1() -> anyhow::Result<()> {
-    let path = Path::new("input_day9.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
-
-    let numbers = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .map(|x| x.parse::<u64>())
-        .collect::<Result<Vec<_>, _>>()?;
-
-    let window_size = 25;
-    let mut window = numbers.iter().take(window_size).collect::<VecDeque<_>>();
-    let mut missing = None;
-    for x in numbers.iter().skip(window_size) {
-        let valid = window
-            .iter()
-            .tuple_combinations::<(_, _)>()
-            .any(|(a, b)| *a + *b == *x);
-
-        if valid {
-            window.pop_front();
-            window.push_back(x);
-        } else {
-            missing = Some(x);
-            break;
+        match self {
+            XmasError::NoInvalidNumber { preamble_size } => write!(
+                f,
+                "Every number is the sum of two of the {} numbers before it.",
+                preamble_size
+            ),
+            XmasError::NoWeakness { invalid } => write!(
+                f,
+                "No contiguous range of at least two numbers sums to {}.",
+                invalid
+            ),
         }
     }
+}
+
+/// Validates an XMAS-encoded stream one number at a time.
+///
+/// Alongside the numbers seen so far, the validator keeps a count of every sum of two
+/// numbers at different positions in the most recent `preamble_size` of them, so checking a
+/// number is a single lookup and sliding the window updates `preamble_size` sums. Sums which
+/// overflow a `u64` can never match a number, so they are left out.
+#[derive(Debug)]
+pub struct XmasValidator {
+    preamble_size: usize,
+    numbers: Vec<u64>,
+    pair_sums: HashMap<u64, usize>,
+    first_invalid: Option<u64>,
+}
 
-    match missing {
-        Some(x) => println!("{}", x),
-        None => return Err(ProblemInvariantError.into()),
+impl XmasValidator {
+    pub fn new(preamble_size: usize) -> XmasValidator {
+        XmasValidator {
+            preamble_size,
+            numbers: Vec::new(),
+            pair_sums: HashMap::new(),
+            first_invalid: None,
+        }
     }
 
-    Ok(())
-}
+    /// Checks whether `x` is the sum of two numbers in the current window, then slides it
+    /// into the window. Numbers which are part of the preamble are always valid.
+    pub fn push(&mut self, x: u64) -> bool {
+        let valid = self.numbers.len() < self.preamble_size || self.pair_sums.contains_key(&x);
+        if !valid && self.first_invalid.is_none() {
+            self.first_invalid = Some(x);
+        }
 
-pub fn problem2() -> anyhow::Result<()> {
-    let path = Path::new("input_day9.txt");
-    let display = path.display();
-
-    let file = match File::open(&path) {
-        Err(why) => panic!("failed to open {}: {}", display, why),
-        Ok(file) => file,
-    };
-
-    let numbers = io::BufReader::new(file)
-        .lines()
-        .collect::<Result<Vec<_>, _>>()?
-        .iter()
-        .map(|x| x.parse::<u64>())
-        .collect::<Result<Vec<_>, _>>()?;
-
-    let window_size = 25;
-    let mut window = numbers.iter().take(window_size).collect::<VecDeque<_>>();
-    let mut missing = None;
-    for x in numbers.iter().skip(window_size) {
-        let valid = window
-            .iter()
-            .tuple_combinations::<(_, _)>()
-            .any(|(a, b)| *a + *b == *x);
-
-        if valid {
-            window.pop_front();
-            window.push_back(x);
-        } else {
-            missing = Some(x);
-            break;
+        if self.preamble_size > 0 {
+            let start = self.numbers.len().saturating_sub(self.preamble_size);
+            let mut window = &self.numbers[start..];
+            if window.len() == self.preamble_size {
+                let (&oldest, rest) = window.split_first().unwrap();
+                for sum in rest.iter().filter_map(|y| oldest.checked_add(*y)) {
+                    let count = self.pair_sums.get_mut(&sum).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        self.pair_sums.remove(&sum);
+                    }
+                }
+                window = rest;
+            }
+            for sum in window.iter().filter_map(|y| x.checked_add(*y)) {
+                *self.pair_sums.entry(sum).or_insert(0) += 1;
+            }
         }
+        self.numbers.push(x);
+
+        valid
     }
 
-    let missing = missing.ok_or(ProblemInvariantError)?;
-    let mut bounds: Option<(u64, u64)> = None;
-    'outer: for n in 2..numbers.len() {
-        for window in numbers.windows(n) {
-            if window.iter().sum::<u64>() == *missing {
-                let min = window.iter().min().unwrap();
-                let max = window.iter().max().unwrap();
-                bounds = Some((*min, *max));
-                break 'outer;
+    /// The first number pushed which was not the sum of two of the `preamble_size` numbers
+    /// before it.
+    pub fn first_invalid(&self) -> Option<u64> {
+        self.first_invalid
+    }
+
+    /// Finds the first contiguous run of at least two numbers pushed so far which sums to
+    /// `target`.
+    ///
+    /// The numbers are non-negative, so the running sum of a window only grows as it is
+    /// extended to the right and only shrinks as it is narrowed from the left, allowing a
+    /// single pass with two pointers. The sum is kept as a `u128`, which no run of `u64`s
+    /// held in memory can overflow.
+    pub fn contiguous_range(&self, target: u64) -> Option<&[u64]> {
+        let numbers = &self.numbers;
+        let target = u128::from(target);
+        let mut start = 0;
+        let mut sum = 0u128;
+        for end in 0..numbers.len() {
+            sum += u128::from(numbers[end]);
+            while sum > target && start < end {
+                sum -= u128::from(numbers[start]);
+                start += 1;
+            }
+            if sum == target && end > start {
+                return Some(&numbers[start..=end]);
             }
         }
+        None
+    }
+
+    /// The sum of the smallest and largest numbers in the first contiguous run which sums to
+    /// the first invalid number.
+    pub fn encryption_weakness(&self) -> Option<u64> {
+        let range = self.contiguous_range(self.first_invalid?)?;
+        let min = range.iter().min()?;
+        let max = range.iter().max()?;
+        min.checked_add(*max)
+    }
+}
+
+impl Extend<u64> for XmasValidator {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, numbers: I) {
+        for x in numbers {
+            self.push(x);
+        }
     }
+}
+
+fn read_validator(path: &Path, preamble_size: usize) -> anyhow::Result<XmasValidator> {
+    let mut validator = XmasValidator::new(preamble_size);
+    validator.extend(read_numbers(path)?);
+    Ok(validator)
+}
+
+fn first_invalid(validator: &XmasValidator) -> Result<u64, XmasError> {
+    validator.first_invalid().ok_or(XmasError::NoInvalidNumber {
+        preamble_size: validator.preamble_size,
+    })
+}
+
+fn encryption_weakness(validator: &XmasValidator) -> Result<u64, XmasError> {
+    let invalid = first_invalid(validator)?;
+    validator
+        .encryption_weakness()
+        .ok_or(XmasError::NoWeakness { invalid })
+}
+
+pub fn problem1() -> anyhow::Result<u64> {
+    let validator = read_validator(Path::new("input_day9.txt"), PREAMBLE_SIZE)?;
+    Ok(first_invalid(&validator)?)
+}
+
+pub fn problem2() -> anyhow::Result<u64> {
+    let validator = read_validator(Path::new("input_day9.txt"), PREAMBLE_SIZE)?;
+    Ok(encryption_weakness(&validator)?)
+}
+
+/// Prints the first invalid number and the encryption weakness of the puzzle input, where
+/// each number must be the sum of two of the `preamble_size` numbers before it.
+pub fn solve(preamble_size: usize) -> anyhow::Result<()> {
+    let validator = read_validator(Path::new("input_day9.txt"), preamble_size)?;
+    println!("{}", first_invalid(&validator)?);
+    println!("{}", encryption_weakness(&validator)?);
+    Ok(())
+}
+
+/// A random XMAS-encrypted sequence of `size` numbers, exactly one of which is not the sum of
+/// two of the 25 numbers before it, but is the sum of a contiguous range of earlier numbers.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, PREAMBLE_SIZE + 1..=1000)?;
 
-    match bounds {
-        Some((min, max)) => println!("{}", min + max),
-        None => return Err(ProblemInvariantError.into()),
+    let invalid_at = rng.gen_range(PREAMBLE_SIZE..size);
+    loop {
+        let mut numbers = (0..PREAMBLE_SIZE)
+            .map(|_| rng.gen_range(1..=100))
+            .collect::<Vec<u64>>();
+        while numbers.len() < size {
+            let window = &numbers[numbers.len() - PREAMBLE_SIZE..];
+            if numbers.len() == invalid_at {
+                let start = rng.gen_range(0..invalid_at - 1);
+                let end = rng.gen_range(start + 2..=invalid_at);
+                let sum = numbers[start..end].iter().sum::<u64>();
+                let is_pair_sum = window
+                    .iter()
+                    .enumerate()
+                    .any(|(i, x)| window[i + 1..].iter().any(|y| x + y == sum));
+                if is_pair_sum {
+                    break;
+                }
+                numbers.push(sum);
+            } else {
+                // Adding up the oldest numbers in the window keeps their growth slow enough
+                // for a long sequence to fit in a `u64`.
+                let i = rng.gen_range(0..5);
+                let j = (i + rng.gen_range(1..5)) % 5;
+                numbers.push(window[i] + window[j]);
+            }
+        }
+
+        if numbers.len() == size {
+            return Ok(numbers.iter().map(|x| format!("{}\n", x)).collect());
+        }
     }
+}
+
+fn read_numbers(path: &Path) -> anyhow::Result<Vec<u64>> {
+    let numbers = records::parse_numbers(1, &input::read_lines(path)?)?;
+    Ok(numbers)
+}
+
+/// Checks that `lines` parse as an XMAS-encrypted sequence of numbers.
+pub(crate) fn check_input(lines: &[&str]) -> anyhow::Result<()> {
+    records::parse_numbers::<u64, _>(1, lines)?;
+    Ok(())
+}
 
+/// Solves both parts of the puzzle for the numbers in `lines`.
+pub(crate) fn check_solutions(lines: &[&str]) -> anyhow::Result<()> {
+    let mut validator = XmasValidator::new(PREAMBLE_SIZE);
+    validator.extend(records::parse_numbers(1, lines)?);
+    let first = first_invalid(&validator);
+    let second = encryption_weakness(&validator);
+    first?;
+    second?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::differential::{self, assert_agree};
+
+    fn validator(numbers: &[u64], preamble_size: usize) -> XmasValidator {
+        let mut validator = XmasValidator::new(preamble_size);
+        validator.extend(numbers.iter().copied());
+        validator
+    }
+
+    fn find_first_invalid(numbers: &[u64], preamble_size: usize) -> Option<u64> {
+        validator(numbers, preamble_size).first_invalid()
+    }
+
+    /// Checks every number against every pair of positions in the window before it.
+    fn brute_force_first_invalid(numbers: &[u64], preamble_size: usize) -> Option<u64> {
+        (preamble_size..numbers.len())
+            .find(|&i| {
+                let window = &numbers[i - preamble_size..i];
+                !(0..window.len())
+                    .any(|j| (j + 1..window.len()).any(|k| window[j] + window[k] == numbers[i]))
+            })
+            .map(|i| numbers[i])
+    }
+
+    /// Sums every range of at least two numbers, by end and then by start.
+    fn brute_force_contiguous_range(numbers: &[u64], target: u64) -> Option<&[u64]> {
+        (1..numbers.len())
+            .flat_map(|end| (0..end).map(move |start| &numbers[start..=end]))
+            .find(|range| range.iter().sum::<u64>() == target)
+    }
+
+    fn random_numbers(rng: &mut StdRng) -> Vec<u64> {
+        (0..rng.gen_range(0..=30))
+            .map(|_| rng.gen_range(0..=20))
+            .collect()
+    }
+
+    #[test]
+    fn test_first_invalid_against_brute_force() {
+        for case in 0..differential::CASES {
+            let mut rng = differential::rng(case);
+            let preamble_size = rng.gen_range(0..=5);
+            let numbers = random_numbers(&mut rng);
+            assert_agree(
+                &numbers,
+                |numbers| brute_force_first_invalid(numbers, preamble_size),
+                |numbers| find_first_invalid(numbers, preamble_size),
+            );
+        }
+    }
+
+    #[test]
+    fn test_contiguous_range_against_brute_force() {
+        for case in 0..differential::CASES {
+            let mut rng = differential::rng(case);
+            let target = rng.gen_range(0..=60);
+            let numbers = random_numbers(&mut rng);
+            assert_agree(
+                &numbers,
+                |numbers| brute_force_contiguous_range(numbers, target).map(<[u64]>::to_vec),
+                |numbers| {
+                    validator(numbers, 0)
+                        .contiguous_range(target)
+                        .map(<[u64]>::to_vec)
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn test_generated_inputs_against_brute_force() {
+        for case in 0..20 {
+            let mut rng = differential::rng(case);
+            let input = generate(rng.gen_range(PREAMBLE_SIZE + 1..=200), &mut rng).unwrap();
+            let numbers =
+                records::parse_numbers::<u64, _>(1, &input.lines().collect::<Vec<_>>()).unwrap();
+            assert_agree(
+                &numbers,
+                |numbers| brute_force_first_invalid(numbers, PREAMBLE_SIZE),
+                |numbers| find_first_invalid(numbers, PREAMBLE_SIZE),
+            );
+        }
+    }
+
+    #[test]
+    fn test_example() {
+        let numbers = [
+            35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309,
+            576,
+        ];
+        let validator = validator(&numbers, 5);
+        assert_eq!(validator.first_invalid(), Some(127));
+        assert_eq!(validator.contiguous_range(127), Some(&[15, 25, 47, 40][..]));
+        assert_eq!(validator.encryption_weakness(), Some(62));
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        // Every pair sum in the window overflows, so nothing after the preamble is valid,
+        // and the running sum overflows a `u64` before the search narrows it again.
+        let numbers = [u64::MAX, u64::MAX - 1, u64::MAX, 1, u64::MAX - 1];
+        let validator = validator(&numbers, 2);
+        assert_eq!(validator.first_invalid(), Some(u64::MAX));
+        assert_eq!(
+            validator.contiguous_range(u64::MAX),
+            Some(&[1, u64::MAX - 1][..])
+        );
+        assert_eq!(validator.encryption_weakness(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_errors() {
+        let every_number_valid = validator(&[1, 2, 3, 5, 8], 2);
+        assert_eq!(
+            first_invalid(&every_number_valid).unwrap_err().to_string(),
+            "Every number is the sum of two of the 2 numbers before it."
+        );
+        let no_weakness = validator(&[1, 2, 4], 2);
+        assert_eq!(
+            encryption_weakness(&no_weakness).unwrap_err().to_string(),
+            "No contiguous range of at least two numbers sums to 4."
+        );
+    }
+}
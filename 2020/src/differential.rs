@@ -0,0 +1,96 @@
+//! Differential testing of the optimised solvers against straightforward reference
+//! implementations.
+//!
+//! Each day's tests generate random inputs, and [`assert_agree`] runs both implementations
+//! on every one. When they disagree the input is shrunk, by repeatedly deleting runs of its
+//! items for as long as the implementations still disagree, so that the failure is reported
+//! on an input small enough to follow by hand.
+
+use std::fmt::Debug;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// The number of random inputs each differential test tries.
+pub const CASES: u64 = 300;
+
+/// A generator for the random inputs of case `case`, seeded so that failures reproduce.
+pub fn rng(case: u64) -> StdRng {
+    StdRng::seed_from_u64(case)
+}
+
+/// Panics if `reference` and `optimised` give different answers for `input`, reporting the
+/// smallest sub-sequence of `input` found on which they still differ.
+pub fn assert_agree<T, R>(input: &[T], reference: impl Fn(&[T]) -> R, optimised: impl Fn(&[T]) -> R)
+where
+    T: Clone + Debug,
+    R: PartialEq + Debug,
+{
+    let disagree = |input: &[T]| reference(input) != optimised(input);
+    if !disagree(input) {
+        return;
+    }
+
+    let shrunk = shrink(input, disagree);
+    panic!(
+        "implementations disagree on {:?} (shrunk from {} items): reference gives {:?}, \
+         optimised gives {:?}",
+        shrunk,
+        input.len(),
+        reference(&shrunk),
+        optimised(&shrunk)
+    );
+}
+
+/// Deletes runs of items from `input`, halving the length of the runs tried whenever none
+/// can be deleted, for as long as `fails` still holds.
+fn shrink<T: Clone>(input: &[T], fails: impl Fn(&[T]) -> bool) -> Vec<T> {
+    let mut input = input.to_vec();
+    let mut chunk = (input.len() / 2).max(1);
+    while !input.is_empty() {
+        let mut deleted = false;
+        let mut start = 0;
+        while start < input.len() {
+            let end = (start + chunk).min(input.len());
+            let candidate = [&input[..start], &input[end..]].concat();
+            if fails(&candidate) {
+                input = candidate;
+                deleted = true;
+            } else {
+                start += chunk;
+            }
+        }
+
+        if chunk > 1 {
+            chunk /= 2;
+        } else if !deleted {
+            break;
+        }
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shrink() {
+        // Fails whenever both a 3 and a 7 are present.
+        let fails = |input: &[u32]| input.contains(&3) && input.contains(&7);
+        let input = [1, 3, 5, 2, 8, 7, 9, 3, 4];
+        assert_eq!(shrink(&input, fails), vec![7, 3]);
+        assert_eq!(shrink(&[0, 1, 2], |_| true), Vec::<u32>::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "implementations disagree on [5]")]
+    fn test_assert_agree_reports_shrunk_input() {
+        let input = [1, 2, 5, 3, 4];
+        assert_agree(
+            &input,
+            |xs: &[u32]| xs.iter().sum::<u32>(),
+            |xs: &[u32]| xs.iter().filter(|&&x| x != 5).sum::<u32>(),
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Entry points for fuzzing the parsers and solvers, shared by the property tests below and
+//! the cargo-fuzz targets in `fuzz/`.
+//!
+//! Whatever the input, a parser or solver must return either a value or an error: it must
+//! never panic, and it must never hang.
+
+use crate::input;
+use crate::{day1, day10, day11, day12, day13, day2, day3, day4, day5, day6, day7, day8, day9};
+
+/// The kinds of input which can be parsed: each day's puzzle input, plus day 4's passport
+/// schema and day 8's boot code source.
+pub const INPUTS: [&str; 15] = [
+    "day1",
+    "day2",
+    "day3",
+    "day4",
+    "day4-schema",
+    "day5",
+    "day6",
+    "day7",
+    "day8",
+    "day8-source",
+    "day9",
+    "day10",
+    "day11",
+    "day12",
+    "day13",
+];
+
+/// Parses `text` as the kind of input called `name`, after the same normalisation as an
+/// input read from a file.
+pub fn parse(name: &str, text: &str) -> anyhow::Result<()> {
+    let lines = input::normalise(text, true);
+    let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+    let source = || input::normalise(text, false).join("\n") + "\n";
+    match name {
+        "day1" => day1::check_input(&lines),
+        "day2" => day2::check_input(&lines),
+        "day3" => day3::check_input(&lines),
+        "day4" => day4::check_input(&lines),
+        "day4-schema" => day4::check_schema(&source()),
+        "day5" => day5::check_input(&lines),
+        "day6" => day6::check_input(&lines),
+        "day7" => day7::check_input(&lines),
+        "day8" => day8::check_input(&lines),
+        "day8-source" => day8::check_source(&source()),
+        "day9" => day9::check_input(&lines),
+        "day10" => day10::check_input(&lines),
+        "day11" => day11::check_input(&lines),
+        "day12" => day12::check_input(&lines),
+        "day13" => day13::check_input(&lines),
+        _ => anyhow::bail!("There is no input called `{}`.", name),
+    }
+}
+
+/// Parses `text` as the kind of input called `name`, like [`parse`], and solves both parts
+/// of that day's puzzle. Passport schemas and boot code sources are only parsed, since no
+/// puzzle takes them as its input.
+pub fn solve(name: &str, text: &str) -> anyhow::Result<()> {
+    let lines = input::normalise(text, true);
+    let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+    match name {
+        "day1" => day1::check_solutions(&lines),
+        "day2" => day2::check_solutions(&lines),
+        "day3" => day3::check_solutions(&lines),
+        "day4" => day4::check_solutions(&lines),
+        "day5" => day5::check_solutions(&lines),
+        "day6" => day6::check_solutions(&lines),
+        "day7" => day7::check_solutions(&lines),
+        "day8" => day8::check_solutions(&lines),
+        "day9" => day9::check_solutions(&lines),
+        "day10" => day10::check_solutions(&lines),
+        "day11" => day11::check_solutions(&lines),
+        "day12" => day12::check_solutions(&lines),
+        "day13" => day13::check_solutions(&lines),
+        _ => parse(name, text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::thread;
+    use std::time::Duration;
+
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseError;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// Far longer than any parser or solver needs for the inputs below.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// A small random input of the kind called `name`, or `None` if there is no generator
+    /// for it.
+    fn generate(name: &str, seed: u64) -> Option<String> {
+        let rng = &mut StdRng::seed_from_u64(seed);
+        let input = match name {
+            "day1" => day1::generate(10, rng),
+            "day2" => day2::generate(10, rng),
+            "day3" => day3::generate(5, rng),
+            "day4" => day4::generate(5, rng),
+            "day5" => day5::generate(10, rng),
+            "day6" => day6::generate(5, rng),
+            "day7" => day7::generate(10, rng),
+            "day8" => day8::generate(10, rng),
+            "day9" => day9::generate(30, rng),
+            "day10" => day10::generate(10, rng),
+            "day11" => day11::generate(6, rng),
+            "day12" => day12::generate(10, rng),
+            "day13" => day13::generate(10, rng),
+            _ => return None,
+        };
+        Some(input.unwrap())
+    }
+
+    /// Applies each `(position, operation, c)` edit to `text` in turn, inserting `c`,
+    /// replacing a character with `c`, or deleting a character.
+    fn mutate(text: &str, edits: &[(usize, u8, char)]) -> String {
+        let mut chars = text.chars().collect::<Vec<_>>();
+        for &(position, operation, c) in edits {
+            let position = position % (chars.len() + 1);
+            match (operation % 3, position < chars.len()) {
+                (0, _) => chars.insert(position, c),
+                (1, true) => chars[position] = c,
+                (2, true) => {
+                    chars.remove(position);
+                }
+                _ => {}
+            }
+        }
+        chars.into_iter().collect()
+    }
+
+    /// Parses and solves `text` on another thread, failing if that panics or doesn't finish.
+    fn solve_promptly(name: &'static str, text: String) -> Result<(), TestCaseError> {
+        let (sender, receiver) = mpsc::channel();
+        let solver = thread::spawn(move || {
+            let _ = solve(name, &text);
+            let _ = sender.send(());
+        });
+        match receiver.recv_timeout(TIMEOUT) {
+            Ok(()) => Ok(()),
+            Err(RecvTimeoutError::Disconnected) => {
+                let panic = solver.join().unwrap_err();
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Err(TestCaseError::fail(format!("panicked: {}", message)))
+            }
+            Err(RecvTimeoutError::Timeout) => Err(TestCaseError::fail("timed out")),
+        }
+    }
+
+    /// Characters which mean something in at least one of the input formats.
+    const ALPHABET: &str = "[.#LRFBNESWxacjmpno0-9a-z:+, \\-\n]{0,300}";
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn test_arbitrary_text(name in prop::sample::select(&INPUTS[..]), text in any::<String>()) {
+            solve_promptly(name, text)?;
+        }
+
+        #[test]
+        fn test_text_from_alphabet(name in prop::sample::select(&INPUTS[..]), text in ALPHABET) {
+            solve_promptly(name, text)?;
+        }
+
+        #[test]
+        fn test_mutated_inputs(
+            name in prop::sample::select(&INPUTS[..]),
+            seed in any::<u64>(),
+            edits in prop::collection::vec((any::<usize>(), any::<u8>(), any::<char>()), 1..8),
+        ) {
+            if let Some(text) = generate(name, seed) {
+                solve_promptly(name, mutate(&text, &edits))?;
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_inputs_parse() {
+        for name in &INPUTS {
+            for seed in 0..10 {
+                if let Some(text) = generate(name, seed) {
+                    if let Err(e) = parse(name, &text) {
+                        panic!("{} input {} failed to parse: {}\n{}", name, seed, e, text);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_inputs_solve() {
+        for name in &INPUTS {
+            for seed in 0..10 {
+                if let Some(text) = generate(name, seed) {
+                    if let Err(e) = solve(name, &text) {
+                        panic!("{} input {} failed to solve: {}\n{}", name, seed, e, text);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_puzzle_inputs_parse() {
+        let schema = std::fs::read_to_string("passport_schema.txt").unwrap();
+        parse("day4-schema", &schema).unwrap();
+        for name in INPUTS.iter().filter(|name| !name.contains('-')) {
+            let text = std::fs::read_to_string(format!("input_{}.txt", name)).unwrap();
+            parse(name, &text).unwrap();
+        }
+        assert!(parse("day14", "").is_err());
+    }
+}
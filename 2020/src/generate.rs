@@ -0,0 +1,47 @@
+//! Support for the per-day `generate` functions, which write random puzzle inputs for
+//! stress tests and benchmarks.
+
+use std::ops::RangeInclusive;
+
+#[derive(Debug)]
+pub struct InvalidSizeError {
+    size: usize,
+    sizes: RangeInclusive<usize>,
+}
+
+impl std::error::Error for InvalidSizeError {}
+
+impl std::fmt::Display for InvalidSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Can't generate an input of size {}, the size must be between {} and {}.",
+            self.size,
+            self.sizes.start(),
+            self.sizes.end()
+        )
+    }
+}
+
+pub fn check_size(size: usize, sizes: RangeInclusive<usize>) -> Result<(), InvalidSizeError> {
+    if sizes.contains(&size) {
+        Ok(())
+    } else {
+        Err(InvalidSizeError { size, sizes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_size() {
+        assert!(check_size(1, 1..=3).is_ok());
+        assert!(check_size(3, 1..=3).is_ok());
+        assert_eq!(
+            check_size(4, 1..=3).unwrap_err().to_string(),
+            "Can't generate an input of size 4, the size must be between 1 and 3."
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Reading puzzle inputs, however they were saved.
+//!
+//! Inputs saved on Windows end their lines with `\r\n`, editors sometimes add a byte order
+//! mark or trailing spaces, and the parsers shouldn't have to care about any of it.
+
+use anyhow::Context;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::Path;
+
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Splits `text` into lines without their `\n` or `\r\n` endings or a byte order mark at
+/// either end, optionally trimming trailing whitespace from each line too.
+pub fn normalise(text: &str, trim_trailing_whitespace: bool) -> Vec<String> {
+    let text = text.strip_prefix(BYTE_ORDER_MARK).unwrap_or(text);
+    let text = text.strip_suffix(BYTE_ORDER_MARK).unwrap_or(text);
+    text.lines()
+        .map(|line| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if trim_trailing_whitespace {
+                line.trim_end()
+            } else {
+                line
+            }
+            .to_string()
+        })
+        .collect()
+}
+
+fn read_text(path: &Path) -> anyhow::Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Reads the lines of the file at `path`, with line endings, a byte order mark and trailing
+/// whitespace removed.
+pub fn read_lines(path: &Path) -> anyhow::Result<Vec<String>> {
+    Ok(normalise(&read_text(path)?, true))
+}
+
+/// Reads the file at `path` as `\n`-separated lines without byte order marks, but otherwise
+/// unchanged.
+pub fn read_source(path: &Path) -> anyhow::Result<String> {
+    let mut source = normalise(&read_text(path)?, false).join("\n");
+    source.push('\n');
+    Ok(source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The input as Notepad might save it: with a byte order mark, `\r\n` line endings and
+    /// the odd trailing space.
+    fn windows(text: &str) -> String {
+        let mut windows = BYTE_ORDER_MARK.to_string();
+        for (i, line) in text.lines().enumerate() {
+            windows += line;
+            if i % 3 == 0 {
+                windows += " \t";
+            }
+            windows += "\r\n";
+        }
+        windows
+    }
+
+    #[test]
+    fn test_normalise() {
+        let text = "\u{feff}abc\r\n\r\nd e  \r\nf\r";
+        assert_eq!(normalise(text, true), vec!["abc", "", "d e", "f"]);
+        assert_eq!(normalise(text, false), vec!["abc", "", "d e  ", "f"]);
+        assert_eq!(normalise("", true), Vec::<String>::new());
+        assert_eq!(normalise("abc\r\nd\u{feff}", true), vec!["abc", "d"]);
+        assert_eq!(normalise("\u{feff}\u{feff}", true), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_windows_inputs() {
+        // Each day's input reads back as the same lines after saving it as Notepad might, so
+        // every day parses it the same.
+        for day in 1..=13 {
+            let path = format!("input_day{}.txt", day);
+            let text = std::fs::read_to_string(&path).unwrap();
+            let lines = read_lines(Path::new(&path)).unwrap();
+            assert_eq!(lines, text.lines().collect::<Vec<_>>(), "{}", path);
+
+            let windows_path =
+                std::env::temp_dir().join(format!("adventofcode-{}-{}", std::process::id(), path));
+            std::fs::write(&windows_path, windows(&text)).unwrap();
+            let windows_lines = read_lines(&windows_path);
+            std::fs::remove_file(&windows_path).unwrap();
+            assert_eq!(windows_lines.unwrap(), lines, "{}", path);
+        }
+    }
+}
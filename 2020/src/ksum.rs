@@ -0,0 +1,269 @@
+//! Finding `k` entries of a list which sum to a target.
+//!
+//! Combinations are made of entries at distinct positions, and two combinations are the same
+//! if they contain the same values, regardless of which positions the values came from.
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug)]
+pub struct KSum {
+    /// The entries in increasing order.
+    sorted: Vec<u64>,
+    /// The entries in their original order.
+    entries: Vec<u64>,
+    k: usize,
+    target: u64,
+}
+
+impl KSum {
+    pub fn new(entries: &[u64], k: usize, target: u64) -> KSum {
+        let mut sorted = entries.to_vec();
+        sorted.sort_unstable();
+        KSum {
+            sorted,
+            entries: entries.to_vec(),
+            k,
+            target,
+        }
+    }
+
+    /// Finds a single combination, or `None` if there are none.
+    ///
+    /// For `k = 2` this is the pair whose later entry comes first in the input.
+    pub fn first(&self) -> Option<Vec<u64>> {
+        if self.k == 2 {
+            let mut seen = HashSet::new();
+            for &x in &self.entries {
+                if let Some(y) = self.target.checked_sub(x) {
+                    if seen.contains(&y) {
+                        return Some(vec![y.min(x), y.max(x)]);
+                    }
+                }
+                seen.insert(x);
+            }
+            return None;
+        }
+
+        let mut result = None;
+        self.search(&mut |combination| {
+            result = Some(combination.to_vec());
+            false
+        });
+        result
+    }
+
+    /// Finds every distinct combination, each in increasing order, in lexicographic order.
+    pub fn all(&self) -> Vec<Vec<u64>> {
+        if self.k == 2 {
+            let mut counts = HashMap::<u64, usize>::new();
+            for &x in &self.entries {
+                *counts.entry(x).or_insert(0) += 1;
+            }
+
+            let mut values = counts.keys().copied().collect::<Vec<_>>();
+            values.sort_unstable();
+            return values
+                .into_iter()
+                .filter_map(|x| {
+                    let y = self.target.checked_sub(x)?;
+                    let available = if x == y { 2 } else { 1 };
+                    if x <= y && counts.get(&y).map_or(0, |&count| count) >= available {
+                        Some(vec![x, y])
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+        }
+
+        let mut result = Vec::new();
+        self.search(&mut |combination| {
+            result.push(combination.to_vec());
+            true
+        });
+        result
+    }
+
+    /// Counts the distinct combinations.
+    pub fn count(&self) -> usize {
+        if self.k == 2 {
+            return self.all().len();
+        }
+
+        let mut count = 0;
+        self.search(&mut |_| {
+            count += 1;
+            true
+        });
+        count
+    }
+
+    /// Visits each distinct combination in lexicographic order until `visit` returns `false`.
+    fn search(&self, visit: &mut dyn FnMut(&[u64]) -> bool) {
+        if self.k == 0 {
+            if self.target == 0 {
+                visit(&[]);
+            }
+            return;
+        }
+
+        let mut prefix = Vec::with_capacity(self.k);
+        self.search_from(0, self.k, self.target, &mut prefix, visit);
+    }
+
+    /// Fixes the smallest remaining entry of the combination, skipping duplicate values, until
+    /// two entries remain, which are found with two pointers converging from either end.
+    /// Returns `false` once `visit` has asked to stop.
+    fn search_from(
+        &self,
+        start: usize,
+        k: usize,
+        target: u64,
+        prefix: &mut Vec<u64>,
+        visit: &mut dyn FnMut(&[u64]) -> bool,
+    ) -> bool {
+        let sorted = &self.sorted;
+
+        if k == 1 {
+            if sorted[start..].binary_search(&target).is_ok() {
+                prefix.push(target);
+                let keep_going = visit(prefix);
+                prefix.pop();
+                return keep_going;
+            }
+            return true;
+        }
+
+        if k == 2 {
+            if sorted.len() < start + 2 {
+                return true;
+            }
+            let (mut lo, mut hi) = (start, sorted.len() - 1);
+            while lo < hi {
+                let sum = sorted[lo].saturating_add(sorted[hi]);
+                if sum < target {
+                    lo += 1;
+                } else if sum > target {
+                    hi -= 1;
+                } else {
+                    prefix.push(sorted[lo]);
+                    prefix.push(sorted[hi]);
+                    let keep_going = visit(prefix);
+                    prefix.truncate(prefix.len() - 2);
+                    if !keep_going {
+                        return false;
+                    }
+
+                    let (x, y) = (sorted[lo], sorted[hi]);
+                    while lo < hi && sorted[lo] == x {
+                        lo += 1;
+                    }
+                    while lo < hi && sorted[hi] == y {
+                        hi -= 1;
+                    }
+                }
+            }
+            return true;
+        }
+
+        for i in start..sorted.len() {
+            if i > start && sorted[i] == sorted[i - 1] {
+                continue;
+            }
+            let remaining = match target.checked_sub(sorted[i]) {
+                Some(remaining) => remaining,
+                None => break,
+            };
+
+            prefix.push(sorted[i]);
+            let keep_going = self.search_from(i + 1, k - 1, remaining, prefix, visit);
+            prefix.pop();
+            if !keep_going {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use rand::Rng;
+
+    use super::*;
+    use crate::differential::{self, assert_agree};
+
+    /// Every distinct combination, found by trying every choice of `k` positions, in
+    /// lexicographic order.
+    fn brute_force_all(entries: &[u64], k: usize, target: u64) -> Vec<Vec<u64>> {
+        fn choose(
+            entries: &[u64],
+            k: usize,
+            target: u64,
+            chosen: &mut Vec<u64>,
+            found: &mut BTreeSet<Vec<u64>>,
+        ) {
+            if chosen.len() == k {
+                if chosen.iter().sum::<u64>() == target {
+                    let mut combination = chosen.clone();
+                    combination.sort_unstable();
+                    found.insert(combination);
+                }
+                return;
+            }
+            for (i, &x) in entries.iter().enumerate() {
+                chosen.push(x);
+                choose(&entries[i + 1..], k, target, chosen, found);
+                chosen.pop();
+            }
+        }
+
+        let mut found = BTreeSet::new();
+        choose(entries, k, target, &mut Vec::new(), &mut found);
+        found.into_iter().collect()
+    }
+
+    /// The pair whose later entry comes first, found with nested loops.
+    fn brute_force_first_pair(entries: &[u64], target: u64) -> Option<Vec<u64>> {
+        for j in 0..entries.len() {
+            for i in 0..j {
+                if entries[i] + entries[j] == target {
+                    return Some(vec![entries[i].min(entries[j]), entries[i].max(entries[j])]);
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        for case in 0..differential::CASES {
+            let mut rng = differential::rng(case);
+            let k = rng.gen_range(0..=4);
+            let target = rng.gen_range(0..=40);
+            let entries = (0..rng.gen_range(0..=12))
+                .map(|_| rng.gen_range(0..=20))
+                .collect::<Vec<u64>>();
+
+            assert_agree(
+                &entries,
+                |entries| {
+                    let all = brute_force_all(entries, k, target);
+                    let first = if k == 2 {
+                        brute_force_first_pair(entries, target)
+                    } else {
+                        all.first().cloned()
+                    };
+                    (first, all.len(), all)
+                },
+                |entries| {
+                    let ksum = KSum::new(entries, k, target);
+                    (ksum.first(), ksum.count(), ksum.all())
+                },
+            );
+        }
+    }
+}
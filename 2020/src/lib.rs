@@ -0,0 +1,21 @@
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+#[cfg(test)]
+mod differential;
+pub mod fuzz;
+mod generate;
+mod input;
+mod ksum;
+mod parser;
+mod records;
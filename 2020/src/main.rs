@@ -1,43 +1,218 @@
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+use std::path::Path;
+
+use adventofcode::{
+    day1, day10, day11, day12, day13, day2, day3, day4, day5, day6, day7, day8, day9,
+};
+use report::Format;
+
+const USAGE: &str = "\
+usage: adventofcode [--format plain|json|table]
+       adventofcode day1 [--target <n>] [--k <n>] [--report first|all|count]
+       adventofcode day2 report <password database>
+       adventofcode day3 survey <map> <slope>...
+       adventofcode day3 render <map> <slope>
+       adventofcode day4 report <passports> [--schema <schema>]
+       adventofcode day5 encode <seat id> [--row-bits <n>] [--column-bits <n>]
+       adventofcode day5 decode <boarding pass> [--row-bits <n>] [--column-bits <n>]
+       adventofcode day5 map <boarding passes> [--row-bits <n>] [--column-bits <n>]
+       adventofcode day6 summary <answers> [--at-least <k>]
+       adventofcode day8 assemble <source>
+       adventofcode day8 disassemble <boot code>
+       adventofcode day8 cfg <boot code>
+       adventofcode day8 trace <boot code> <trace.jsonl> [--max-steps <n>]
+       adventofcode day8 repair <boot code>
+       adventofcode day9 [--preamble <n>]
+       adventofcode day10 arrangements <adapters> [--gaps <g,...>] [--list <n>]
+                                       [--sample <n>] [--seed <n>]
+       adventofcode generate <day> [--size <n>] [--seed <n>]";
+
+#[derive(Debug)]
+struct UsageError;
+
+impl std::error::Error for UsageError {}
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", USAGE)
+    }
+}
+
+/// Collects `--name value` pairs, rejecting anything else.
+fn parse_options<'a>(args: &[&'a str]) -> Result<HashMap<&'a str, &'a str>, UsageError> {
+    let mut options = HashMap::new();
+    for pair in args.chunks(2) {
+        match pair {
+            [name, value] if name.starts_with("--") => {
+                options.insert(&name[2..], *value);
+            }
+            _ => return Err(UsageError),
+        }
+    }
+    Ok(options)
+}
 
 fn main() -> anyhow::Result<()> {
-    day1::problem1()?;
-    day1::problem2()?;
-    day2::problem1()?;
-    day2::problem2()?;
-    day3::problem1()?;
-    day3::problem2()?;
-    day4::problem1()?;
-    day4::problem2()?;
-    day5::problem1()?;
-    day5::problem2()?;
-    day6::problem1()?;
-    day6::problem2()?;
-    day7::problem1()?;
-    day7::problem2()?;
-    day8::problem1()?;
-    day8::problem2()?;
-    day9::problem1()?;
-    day9::problem2()?;
-    day10::problem1()?;
-    day10::problem2()?;
-    day11::problem1()?;
-    day11::problem2()?;
-    day12::problem1()?;
-    day12::problem2()?;
-    day13::problem1()?;
-    day13::problem2()?;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["day1", options @ ..] => {
+            let options = parse_options(options)?;
+            let target = options.get("target").map_or(Ok(2020), |s| s.parse())?;
+            let k = options.get("k").map_or(Ok(2), |s| s.parse())?;
+            let report = options
+                .get("report")
+                .map_or(Ok(day1::Report::First), |s| s.parse())?;
+            day1::solve(target, k, report)
+        }
+        ["day2", "report", path] => day2::report(Path::new(path)),
+        ["day3", "survey", path, slopes @ ..] => {
+            let slopes = slopes
+                .iter()
+                .map(|s| s.parse())
+                .collect::<Result<Vec<_>, _>>()?;
+            day3::survey(Path::new(path), &slopes)
+        }
+        ["day3", "render", path, slope] => day3::render(Path::new(path), slope.parse()?),
+        ["day4", "report", path, options @ ..] => {
+            let options = parse_options(options)?;
+            let schema_path = options.get("schema").map(Path::new);
+            day4::report(Path::new(path), schema_path)
+        }
+        ["day5", command, argument, options @ ..] => {
+            let options = parse_options(options)?;
+            let row_bits = options.get("row-bits").map_or(Ok(7), |s| s.parse())?;
+            let column_bits = options.get("column-bits").map_or(Ok(3), |s| s.parse())?;
+            let geometry = day5::PlaneGeometry::new(row_bits, column_bits)?;
+            match *command {
+                "encode" => day5::encode(argument.parse()?, geometry),
+                "decode" => day5::decode(argument, geometry),
+                "map" => day5::seat_map(Path::new(argument), geometry),
+                _ => Err(UsageError.into()),
+            }
+        }
+        ["day6", "summary", path, options @ ..] => {
+            let options = parse_options(options)?;
+            let k = options.get("at-least").map(|s| s.parse()).transpose()?;
+            day6::summary(Path::new(path), k)
+        }
+        ["day8", "assemble", path] => day8::assemble(Path::new(path)),
+        ["day8", "disassemble", path] => day8::disassemble(Path::new(path)),
+        ["day8", "cfg", path] => day8::export_cfg(Path::new(path)),
+        ["day8", "trace", path, trace_path, options @ ..] => {
+            let options = parse_options(options)?;
+            let max_steps = options.get("max-steps").map(|s| s.parse()).transpose()?;
+            day8::trace(Path::new(path), Path::new(trace_path), max_steps)
+        }
+        ["day8", "repair", path] => day8::repair(Path::new(path)),
+        ["day9", options @ ..] => {
+            let options = parse_options(options)?;
+            let preamble_size = options.get("preamble").map_or(Ok(25), |s| s.parse())?;
+            day9::solve(preamble_size)
+        }
+        ["day10", "arrangements", path, options @ ..] => {
+            let options = parse_options(options)?;
+            let gaps = options
+                .get("gaps")
+                .map(|s| s.split(',').map(str::parse).collect::<Result<Vec<_>, _>>())
+                .transpose()?;
+            let list = options.get("list").map_or(Ok(0), |s| s.parse())?;
+            let sample = options.get("sample").map_or(Ok(0), |s| s.parse())?;
+            let seed = options.get("seed").map_or(Ok(0), |s| s.parse())?;
+            day10::arrangements(Path::new(path), gaps.as_deref(), list, sample, seed)
+        }
+        ["generate", day, options @ ..] => {
+            let options = parse_options(options)?;
+            let size = options.get("size").map(|s| s.parse()).transpose()?;
+            let seed = options.get("seed").map_or(Ok(0), |s| s.parse())?;
+            generate(day, size, seed)
+        }
+        options => {
+            let options = parse_options(options)?;
+            let format = options
+                .get("format")
+                .map_or(Ok(Format::Plain), |s| s.parse())?;
+            run_all(format)
+        }
+    }
+}
+
+/// Prints a random input for `day`, of the given size or else one comparable to the
+/// puzzle's own input.
+fn generate(day: &str, size: Option<usize>, seed: u64) -> anyhow::Result<()> {
+    type Generator = fn(usize, &mut StdRng) -> anyhow::Result<String>;
+    let (generator, default_size): (Generator, usize) = match day {
+        "day1" => (day1::generate, 200),
+        "day2" => (day2::generate, 1000),
+        "day3" => (day3::generate, 323),
+        "day4" => (day4::generate, 250),
+        "day5" => (day5::generate, 800),
+        "day6" => (day6::generate, 500),
+        "day7" => (day7::generate, 500),
+        "day8" => (day8::generate, 600),
+        "day9" => (day9::generate, 1000),
+        "day10" => (day10::generate, 100),
+        "day11" => (day11::generate, 95),
+        "day12" => (day12::generate, 780),
+        "day13" => (day13::generate, 60),
+        _ => return Err(UsageError.into()),
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    print!("{}", generator(size.unwrap_or(default_size), &mut rng)?);
+    Ok(())
+}
+
+/// Runs every solver, reporting each answer in `format`, and fails if any solver did.
+fn run_all(format: Format) -> anyhow::Result<()> {
+    let outcomes = [
+        report::run(2020, 1, 1, day1::problem1),
+        report::run(2020, 1, 2, day1::problem2),
+        report::run(2020, 2, 1, day2::problem1),
+        report::run(2020, 2, 2, day2::problem2),
+        report::run(2020, 3, 1, day3::problem1),
+        report::run(2020, 3, 2, day3::problem2),
+        report::run(2020, 4, 1, day4::problem1),
+        report::run(2020, 4, 2, day4::problem2),
+        report::run(2020, 5, 1, day5::problem1),
+        report::run(2020, 5, 2, day5::problem2),
+        report::run(2020, 6, 1, day6::problem1),
+        report::run(2020, 6, 2, day6::problem2),
+        report::run(2020, 7, 1, day7::problem1),
+        report::run(2020, 7, 2, day7::problem2),
+        report::run(2020, 8, 1, day8::problem1),
+        report::run(2020, 8, 2, day8::problem2),
+        report::run(2020, 9, 1, day9::problem1),
+        report::run(2020, 9, 2, day9::problem2),
+        report::run(2020, 10, 1, day10::problem1),
+        report::run(2020, 10, 2, day10::problem2),
+        report::run(2020, 11, 1, day11::problem1),
+        report::run(2020, 11, 2, day11::problem2),
+        report::run(2020, 12, 1, day12::problem1),
+        report::run(2020, 12, 2, day12::problem2),
+        report::run(2020, 13, 1, day13::problem1),
+        report::run(2020, 13, 2, day13::problem2),
+    ];
+    print!("{}", report::render(format, &outcomes));
+
+    let failures = outcomes
+        .iter()
+        .filter_map(|outcome| Some((outcome, outcome.answer.as_ref().err()?)))
+        .collect::<Vec<_>>();
+    if let Some((outcome, error)) = failures.first() {
+        anyhow::bail!(
+            "{} of {} solvers failed, first day {} part {}: {}",
+            failures.len(),
+            outcomes.len(),
+            outcome.day,
+            outcome.part,
+            error
+        );
+    }
     Ok(())
 }
@@ -0,0 +1,296 @@
+//! A small parser-combinator library for the line formats of the puzzles.
+//!
+//! A parser is any function from the remaining input to either a value and the input left
+//! over, or a failure. The functions here build parsers for literals, integers and words and
+//! combine them into sequences, alternatives and separated lists, so that a line format can
+//! be written down much like the regular expression it replaces:
+//!
+//! ```text
+//! // 1-3 a: abcde
+//! let entry = pair(
+//!     pair(terminated(unsigned(), literal("-")), terminated(unsigned(), literal(" "))),
+//!     pair(terminated(letter(), literal(": ")), word()),
+//! );
+//! ```
+//!
+//! `parse` runs a parser over a whole line and reports where in the line it failed.
+
+/// The reason a parser failed, relative to the end of its input so that it can be turned
+/// into a position once the whole input is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    remaining: usize,
+    expected: String,
+}
+
+impl Failure {
+    fn new(input: &str, expected: impl Into<String>) -> Failure {
+        Failure {
+            remaining: input.len(),
+            expected: expected.into(),
+        }
+    }
+}
+
+pub type ParseResult<'a, T> = Result<(T, &'a str), Failure>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// The byte offset in the input at which parsing failed.
+    pub position: usize,
+    pub expected: String,
+}
+
+impl std::error::Error for ParseError {}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "expected {} at column {}",
+            self.expected,
+            self.position + 1
+        )
+    }
+}
+
+pub trait Parser<'a, T> {
+    fn parse(&self, input: &'a str) -> ParseResult<'a, T>;
+}
+
+impl<'a, T, F> Parser<'a, T> for F
+where
+    F: Fn(&'a str) -> ParseResult<'a, T>,
+{
+    fn parse(&self, input: &'a str) -> ParseResult<'a, T> {
+        self(input)
+    }
+}
+
+/// Runs `parser` over all of `input`, failing if anything is left over.
+pub fn parse<'a, T>(parser: &impl Parser<'a, T>, input: &'a str) -> Result<T, ParseError> {
+    let error = |failure: Failure| ParseError {
+        position: input.len() - failure.remaining,
+        expected: failure.expected,
+    };
+    match parser.parse(input) {
+        Ok((value, "")) => Ok(value),
+        Ok((_, rest)) => Err(error(Failure::new(rest, "end of line"))),
+        Err(failure) => Err(error(failure)),
+    }
+}
+
+/// Matches `expected` exactly.
+pub fn literal<'a>(expected: &'static str) -> impl Parser<'a, &'a str> {
+    move |input: &'a str| match input.strip_prefix(expected) {
+        Some(rest) => Ok((&input[..expected.len()], rest)),
+        None => Err(Failure::new(input, format!("`{}`", expected))),
+    }
+}
+
+/// Matches the longest non-empty prefix whose characters all satisfy `predicate`.
+fn take_while1<'a>(
+    predicate: impl Fn(char) -> bool,
+    expected: &'static str,
+) -> impl Parser<'a, &'a str> {
+    move |input: &'a str| {
+        let end = input.find(|c| !predicate(c)).unwrap_or(input.len());
+        if end == 0 {
+            Err(Failure::new(input, expected))
+        } else {
+            Ok((&input[..end], &input[end..]))
+        }
+    }
+}
+
+/// Matches one or more ASCII letters.
+pub fn word<'a>() -> impl Parser<'a, &'a str> {
+    take_while1(|c| c.is_ascii_alphabetic(), "a word")
+}
+
+/// Matches a single ASCII letter.
+pub fn letter<'a>() -> impl Parser<'a, char> {
+    move |input: &'a str| match input.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => Ok((c, &input[1..])),
+        _ => Err(Failure::new(input, "a letter")),
+    }
+}
+
+/// Matches one or more decimal digits, as a number which must fit in a `T`.
+pub fn unsigned<'a, T: std::str::FromStr>() -> impl Parser<'a, T> {
+    move |input: &'a str| {
+        let (digits, rest) = take_while1(|c| c.is_ascii_digit(), "a number").parse(input)?;
+        let value = digits
+            .parse()
+            .map_err(|_| Failure::new(input, "a smaller number"))?;
+        Ok((value, rest))
+    }
+}
+
+/// Matches a `+` or `-` sign followed by one or more decimal digits, as a number which must
+/// fit in a `T`.
+pub fn signed<'a, T: std::str::FromStr>() -> impl Parser<'a, T> {
+    move |input: &'a str| {
+        let (digits, rest) = recognize(pair(
+            alt(literal("+"), literal("-")),
+            take_while1(|c| c.is_ascii_digit(), "a number"),
+        ))
+        .parse(input)?;
+        let value = digits
+            .parse()
+            .map_err(|_| Failure::new(input, "a smaller number"))?;
+        Ok((value, rest))
+    }
+}
+
+/// Matches whichever of `keywords` comes first in the input, trying them in order.
+pub fn one_of<'a>(keywords: &'static [&'static str]) -> impl Parser<'a, &'a str> {
+    move |input: &'a str| {
+        keywords
+            .iter()
+            .find_map(|keyword| literal(keyword).parse(input).ok())
+            .ok_or_else(|| {
+                let keywords = keywords
+                    .iter()
+                    .map(|keyword| format!("`{}`", keyword))
+                    .collect::<Vec<_>>();
+                Failure::new(input, format!("one of {}", keywords.join(", ")))
+            })
+    }
+}
+
+/// Matches `first` or, if that fails, `second`. If both fail the error from whichever got
+/// further is reported.
+pub fn alt<'a, T>(first: impl Parser<'a, T>, second: impl Parser<'a, T>) -> impl Parser<'a, T> {
+    move |input: &'a str| {
+        first.parse(input).or_else(|e1| {
+            second
+                .parse(input)
+                .map_err(|e2| match e1.remaining.cmp(&e2.remaining) {
+                    std::cmp::Ordering::Less => e1,
+                    std::cmp::Ordering::Greater => e2,
+                    std::cmp::Ordering::Equal => Failure {
+                        remaining: e1.remaining,
+                        expected: format!("{} or {}", e1.expected, e2.expected),
+                    },
+                })
+        })
+    }
+}
+
+/// Matches `first` and then `second`, keeping both values.
+pub fn pair<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, (A, B)> {
+    move |input: &'a str| {
+        let (a, rest) = first.parse(input)?;
+        let (b, rest) = second.parse(rest)?;
+        Ok(((a, b), rest))
+    }
+}
+
+/// Matches `prefix` and then `parser`, keeping only the value of `parser`.
+pub fn preceded<'a, A, T>(
+    prefix: impl Parser<'a, A>,
+    parser: impl Parser<'a, T>,
+) -> impl Parser<'a, T> {
+    map(pair(prefix, parser), |(_, value)| value)
+}
+
+/// Matches `parser` and then `suffix`, keeping only the value of `parser`.
+pub fn terminated<'a, T, B>(
+    parser: impl Parser<'a, T>,
+    suffix: impl Parser<'a, B>,
+) -> impl Parser<'a, T> {
+    map(pair(parser, suffix), |(value, _)| value)
+}
+
+/// Matches `parser` and transforms its value with `f`.
+pub fn map<'a, T, U>(parser: impl Parser<'a, T>, f: impl Fn(T) -> U) -> impl Parser<'a, U> {
+    move |input: &'a str| {
+        let (value, rest) = parser.parse(input)?;
+        Ok((f(value), rest))
+    }
+}
+
+/// Matches `parser`, but returns the input it consumed rather than its value.
+pub fn recognize<'a, T>(parser: impl Parser<'a, T>) -> impl Parser<'a, &'a str> {
+    move |input: &'a str| {
+        let (_, rest) = parser.parse(input)?;
+        Ok((&input[..input.len() - rest.len()], rest))
+    }
+}
+
+/// Matches one or more `item`s separated by `separator`.
+pub fn separated<'a, T, S>(
+    item: impl Parser<'a, T>,
+    separator: impl Parser<'a, S>,
+) -> impl Parser<'a, Vec<T>> {
+    move |input: &'a str| {
+        let (first, mut rest) = item.parse(input)?;
+        let mut items = vec![first];
+        while let Ok((_, after_separator)) = separator.parse(rest) {
+            let (next, after_item) = item.parse(after_separator)?;
+            items.push(next);
+            rest = after_item;
+        }
+        Ok((items, rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literals_and_sequences() {
+        let parser = pair(terminated(word(), literal(": ")), unsigned::<u32>());
+        assert_eq!(parse(&parser, "abc: 42"), Ok(("abc", 42)));
+        assert_eq!(
+            parse(&parser, "abc 42"),
+            Err(ParseError {
+                position: 3,
+                expected: "`: `".to_string()
+            })
+        );
+        assert_eq!(
+            parse(&parser, "abc: 42x"),
+            Err(ParseError {
+                position: 7,
+                expected: "end of line".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(parse(&unsigned::<u8>(), "255"), Ok(255));
+        assert!(parse(&unsigned::<u8>(), "256").is_err());
+        assert!(parse(&unsigned::<u8>(), "+1").is_err());
+        assert_eq!(parse(&signed::<i32>(), "-12"), Ok(-12));
+        assert_eq!(parse(&signed::<i32>(), "+12"), Ok(12));
+        assert_eq!(parse(&signed::<i32>(), "12").unwrap_err().position, 0);
+        assert_eq!(parse(&signed::<i32>(), "+").unwrap_err().position, 1);
+    }
+
+    #[test]
+    fn test_alternatives() {
+        let parser = alt(
+            map(literal("none"), |_| Vec::new()),
+            separated(unsigned::<u32>(), literal(", ")),
+        );
+        assert_eq!(parse(&parser, "none"), Ok(vec![]));
+        assert_eq!(parse(&parser, "1, 2, 3"), Ok(vec![1, 2, 3]));
+        assert_eq!(
+            parse(&parser, "x"),
+            Err(ParseError {
+                position: 0,
+                expected: "`none` or a number".to_string()
+            })
+        );
+        assert_eq!(parse(&parser, "1, x").unwrap_err().position, 3);
+        assert_eq!(parse(&one_of(&["acc", "jmp"]), "jmp"), Ok("jmp"));
+        assert!(parse(&one_of(&["acc", "jmp"]), "nop").is_err());
+    }
+}
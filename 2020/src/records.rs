@@ -0,0 +1,169 @@
+//! Helpers for the record-based formats that several days share: paragraphs separated by
+//! blank lines, `key:value` fields separated by whitespace, and one number per line.
+//!
+//! Line numbers passed in and reported in errors are 1-based, so that errors can point at
+//! the input file directly.
+
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub struct KeyValueError {
+    line: usize,
+    token: String,
+}
+
+impl std::error::Error for KeyValueError {}
+
+impl std::fmt::Display for KeyValueError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: expected `key:value`, found `{}`",
+            self.line, self.token
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseNumberError {
+    Invalid { line: usize, text: String },
+    Overflow { line: usize, text: String },
+}
+
+impl std::error::Error for ParseNumberError {}
+
+impl std::fmt::Display for ParseNumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseNumberError::Invalid { line, text } => {
+                write!(f, "line {}: `{}` is not a number", line, text)
+            }
+            ParseNumberError::Overflow { line, text } => {
+                write!(f, "line {}: `{}` is out of range", line, text)
+            }
+        }
+    }
+}
+
+fn is_blank<S: AsRef<str>>(line: &S) -> bool {
+    line.as_ref().trim().is_empty()
+}
+
+/// Splits `lines` into paragraphs separated by one or more blank lines, along with the line
+/// number each paragraph starts on.
+pub fn paragraphs<S: AsRef<str>>(lines: &[S]) -> impl Iterator<Item = (usize, &[S])> {
+    let mut start = 0;
+    std::iter::from_fn(move || {
+        while start < lines.len() && is_blank(&lines[start]) {
+            start += 1;
+        }
+        if start == lines.len() {
+            return None;
+        }
+        let end = lines[start..]
+            .iter()
+            .position(is_blank)
+            .map_or(lines.len(), |n| start + n);
+        let paragraph = (start + 1, &lines[start..end]);
+        start = end;
+        Some(paragraph)
+    })
+}
+
+/// Parses whitespace-separated `key:value` fields, in order, from `lines` starting on line
+/// `first_line`. Values may contain further colons.
+pub fn parse_key_values<S: AsRef<str>>(
+    first_line: usize,
+    lines: &[S],
+) -> Result<Vec<(&str, &str)>, KeyValueError> {
+    let mut fields = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        for token in line.as_ref().split_whitespace() {
+            let field = token.split_once(':').ok_or_else(|| KeyValueError {
+                line: first_line + i,
+                token: token.to_string(),
+            })?;
+            fields.push(field);
+        }
+    }
+    Ok(fields)
+}
+
+/// Parses one integer per line from `lines` starting on line `first_line`.
+pub fn parse_numbers<T, S>(first_line: usize, lines: &[S]) -> Result<Vec<T>, ParseNumberError>
+where
+    T: FromStr<Err = ParseIntError>,
+    S: AsRef<str>,
+{
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line_number = first_line + i;
+            let text = line.as_ref().trim();
+            text.parse().map_err(|e: ParseIntError| match e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                    ParseNumberError::Overflow {
+                        line: line_number,
+                        text: text.to_string(),
+                    }
+                }
+                _ => ParseNumberError::Invalid {
+                    line: line_number,
+                    text: text.to_string(),
+                },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paragraphs() {
+        let lines = ["", "a", "b", "", "", "c", " ", "d", ""];
+        let paragraphs = paragraphs(&lines).collect::<Vec<_>>();
+        assert_eq!(
+            paragraphs,
+            vec![(2, &lines[1..3]), (6, &lines[5..6]), (8, &lines[7..8])]
+        );
+        assert_eq!(super::paragraphs::<&str>(&[]).count(), 0);
+    }
+
+    #[test]
+    fn test_parse_key_values() {
+        assert_eq!(
+            parse_key_values(3, &["a:1 b:2", "c:http://x"]),
+            Ok(vec![("a", "1"), ("b", "2"), ("c", "http://x")])
+        );
+        assert_eq!(
+            parse_key_values(3, &["a:1", "b:2 c"]),
+            Err(KeyValueError {
+                line: 4,
+                token: "c".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(parse_numbers::<u64, _>(1, &["1", "22"]), Ok(vec![1, 22]));
+        assert_eq!(
+            parse_numbers::<u64, _>(1, &["1", "2x"]),
+            Err(ParseNumberError::Invalid {
+                line: 2,
+                text: "2x".to_string()
+            })
+        );
+        assert_eq!(
+            parse_numbers::<u8, _>(5, &["256"]),
+            Err(ParseNumberError::Overflow {
+                line: 5,
+                text: "256".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,16 @@
+//! Feeds arbitrary text to every parser, using the first byte to choose which.
+//!
+//! Run with `cargo fuzz run parsers` from the `2022` directory.
+
+#![no_main]
+
+use adventofcode::fuzz::{parse, INPUTS};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((&selector, text)) = data.split_first() {
+        if let Ok(text) = std::str::from_utf8(text) {
+            let _ = parse(INPUTS[selector as usize % INPUTS.len()], text);
+        }
+    }
+});
@@ -1,51 +1,242 @@
-use crate::file_wrappers::get_lines_from_embedded_file;
+use crate::file_wrappers::{get_embedded_text, trimmed_lines};
+use crate::generate::check_size;
+use crate::records::{paragraphs, parse_numbers, ParseNumberError};
 
+use rand::rngs::StdRng;
+use rand::Rng;
 use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 
 #[derive(Debug)]
 pub struct Elf {
     snacks: Vec<u32>,
 }
 
-fn get_elves() -> anyhow::Result<Vec<Elf>> {
-    let lines = get_lines_from_embedded_file("input_day01.txt")?;
-    let numbers: Vec<_> = lines.iter().map(|s| str::parse::<u32>(s).ok()).collect();
-    let elves: Vec<_> = numbers
-        .split(|x| x.is_none())
-        .map(|xs| Elf {
-            snacks: xs.iter().map(|x| x.unwrap()).collect(),
+impl Elf {
+    pub fn snacks(&self) -> &[u32] {
+        &self.snacks
+    }
+
+    pub fn total(&self) -> u32 {
+        self.snacks.iter().sum()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub count: usize,
+    pub total: u64,
+    pub mean: f64,
+    pub median: f64,
+}
+
+/// The elves in the order they appear in the input, so that an elf's index is stable.
+#[derive(Debug)]
+pub struct Inventory {
+    elves: Vec<Elf>,
+}
+
+impl Inventory {
+    pub fn new(elves: Vec<Elf>) -> Inventory {
+        Inventory { elves }
+    }
+
+    pub fn len(&self) -> usize {
+        self.elves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elves.is_empty()
+    }
+
+    pub fn elf(&self, index: usize) -> Option<&Elf> {
+        self.elves.get(index)
+    }
+
+    /// The indices and totals of the `k` elves carrying the most calories, most first.
+    /// Elves with equal totals are ordered by index.
+    pub fn top(&self, k: usize) -> Vec<(usize, u32)> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        for (index, elf) in self.elves.iter().enumerate() {
+            heap.push((Reverse(elf.total()), index));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|(Reverse(total), index)| (index, total))
+            .collect()
+    }
+
+    /// The 1-based rank of the elf at `index`, where elves with equal totals share a rank.
+    pub fn rank(&self, index: usize) -> Option<usize> {
+        let total = self.elf(index)?.total();
+        Some(self.elves.iter().filter(|e| e.total() > total).count() + 1)
+    }
+
+    pub fn stats(&self) -> Option<Stats> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut totals: Vec<_> = self.elves.iter().map(Elf::total).collect();
+        totals.sort_unstable();
+        let count = totals.len();
+        let total: u64 = totals.iter().map(|&t| u64::from(t)).sum();
+        let median = if count % 2 == 1 {
+            f64::from(totals[count / 2])
+        } else {
+            (f64::from(totals[count / 2 - 1]) + f64::from(totals[count / 2])) / 2.0
+        };
+
+        Some(Stats {
+            count,
+            total,
+            mean: total as f64 / count as f64,
+            median,
         })
-        .collect();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseElvesError {
+    InvalidNumber { line: usize, text: String },
+    Overflow { line: usize },
+}
+
+impl From<ParseNumberError> for ParseElvesError {
+    fn from(e: ParseNumberError) -> Self {
+        match e {
+            ParseNumberError::Invalid { line, text } => {
+                ParseElvesError::InvalidNumber { line, text }
+            }
+            ParseNumberError::Overflow { line, .. } => ParseElvesError::Overflow { line },
+        }
+    }
+}
+
+impl std::error::Error for ParseElvesError {}
+
+impl std::fmt::Display for ParseElvesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseElvesError::InvalidNumber { line, text } => {
+                write!(f, "line {}: `{}` is not a number of calories", line, text)
+            }
+            ParseElvesError::Overflow { line } => {
+                write!(f, "line {}: the elf's calories overflow", line)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct NoElvesError;
+
+impl std::error::Error for NoElvesError {}
+
+impl std::fmt::Display for NoElvesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "There are no elves in the inventory.")
+    }
+}
+
+/// Parses one calorie count per line, with elves separated by blank lines. Line numbers in
+/// errors are 1-based.
+fn parse_elves<'a>(lines: impl IntoIterator<Item = &'a str>) -> Result<Vec<Elf>, ParseElvesError> {
+    paragraphs(lines)
+        .map(|(first_line, paragraph)| {
+            let snacks: Vec<u32> = parse_numbers(first_line, &paragraph)?;
+            let mut total = 0u32;
+            for (i, &calories) in snacks.iter().enumerate() {
+                total = total
+                    .checked_add(calories)
+                    .ok_or(ParseElvesError::Overflow {
+                        line: first_line + i,
+                    })?;
+            }
+            Ok(Elf { snacks })
+        })
+        .collect()
+}
+
+fn get_elves() -> anyhow::Result<Vec<Elf>> {
+    let input = get_embedded_text("input_day01.txt")?;
+    let elves = parse_elves(input.trimmed_lines())?;
     Ok(elves)
 }
 
-fn total_snacks(elf: &Elf) -> u32 {
-    elf.snacks.iter().sum()
+pub fn get_inventory() -> anyhow::Result<Inventory> {
+    Ok(Inventory::new(get_elves()?))
 }
 
 pub fn problem1() -> anyhow::Result<u32> {
-    let elves = get_elves()?;
+    Ok(max_total(&get_inventory()?)?)
+}
 
-    let max_snacks = elves
+pub fn problem2() -> anyhow::Result<u64> {
+    Ok(top_three_total(&get_inventory()?)?)
+}
+
+/// The most calories carried by any one elf.
+fn max_total(inventory: &Inventory) -> Result<u32, NoElvesError> {
+    let (_, total) = *inventory.top(1).first().ok_or(NoElvesError)?;
+    Ok(total)
+}
+
+/// The calories carried by the three elves carrying the most, or by every elf if there are
+/// fewer than three.
+fn top_three_total(inventory: &Inventory) -> Result<u64, NoElvesError> {
+    if inventory.is_empty() {
+        return Err(NoElvesError);
+    }
+    Ok(inventory
+        .top(3)
         .iter()
-        .map(total_snacks)
-        .max()
-        .expect("There must be at least one elf.");
-    Ok(max_snacks)
+        .map(|&(_, total)| u64::from(total))
+        .sum())
+}
+
+/// A random inventory of `size` elves, each carrying between 1 and 15 snacks.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=100_000)?;
+
+    let elves: Vec<String> = (0..size)
+        .map(|_| {
+            (0..rng.gen_range(1..=15))
+                .map(|_| format!("{}\n", rng.gen_range(1000..=70000)))
+                .collect()
+        })
+        .collect();
+    Ok(elves.join("\n"))
 }
 
-pub fn problem2() -> anyhow::Result<u32> {
-    let mut elves = get_elves()?;
-    elves.sort_by_key(|e| Reverse(total_snacks(e)));
-    let top_three = elves.iter().take(3);
-    let top_three_snacks: u32 = top_three.map(total_snacks).sum();
-    Ok(top_three_snacks)
+/// Checks that `text` parses as an inventory.
+pub(crate) fn check_input(text: &str) -> anyhow::Result<()> {
+    parse_elves(trimmed_lines(text))?;
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn inventory(elves: &[&[u32]]) -> Inventory {
+        Inventory::new(
+            elves
+                .iter()
+                .map(|snacks| Elf {
+                    snacks: snacks.to_vec(),
+                })
+                .collect(),
+        )
+    }
+
     #[test]
     fn test_problem1() {
         assert_eq!(problem1().unwrap(), 70374);
@@ -55,4 +246,105 @@ mod tests {
     fn test_problem2() {
         assert_eq!(problem2().unwrap(), 204610);
     }
+
+    #[test]
+    fn test_totals() {
+        let inventory = inventory(&[&[u32::MAX], &[1], &[u32::MAX]]);
+        assert_eq!(max_total(&inventory), Ok(u32::MAX));
+        assert_eq!(top_three_total(&inventory), Ok(2 * u64::from(u32::MAX) + 1));
+
+        let empty = Inventory::new(Vec::new());
+        assert_eq!(max_total(&empty), Err(NoElvesError));
+        assert_eq!(top_three_total(&empty), Err(NoElvesError));
+    }
+
+    #[test]
+    fn test_top() {
+        let inventory = inventory(&[
+            &[1000, 2000, 3000],
+            &[4000],
+            &[5000, 6000],
+            &[7000, 8000, 9000],
+            &[10000],
+        ]);
+        assert_eq!(inventory.top(3), vec![(3, 24000), (2, 11000), (4, 10000)]);
+        assert_eq!(inventory.top(0), vec![]);
+        assert_eq!(inventory.top(10).len(), 5);
+    }
+
+    #[test]
+    fn test_top_ties() {
+        let inventory = inventory(&[&[5], &[7], &[5], &[7]]);
+        assert_eq!(inventory.top(3), vec![(1, 7), (3, 7), (0, 5)]);
+        assert_eq!(inventory.rank(3), Some(1));
+        assert_eq!(inventory.rank(2), Some(3));
+        assert_eq!(inventory.rank(4), None);
+    }
+
+    #[test]
+    fn test_parse_elves() {
+        let elves = parse_elves(["1", "2", "", "3", "", "", "4", ""]).unwrap();
+        let snacks: Vec<_> = elves.iter().map(Elf::snacks).collect();
+        assert_eq!(snacks, vec![&[1, 2][..], &[3], &[4]]);
+    }
+
+    #[test]
+    fn test_parse_elves_invalid_number() {
+        assert_eq!(
+            parse_elves(["1000", "12a4", "3000"]).unwrap_err(),
+            ParseElvesError::InvalidNumber {
+                line: 2,
+                text: "12a4".to_string()
+            }
+        );
+        assert_eq!(
+            parse_elves(["1000", "", "-5"]).unwrap_err(),
+            ParseElvesError::InvalidNumber {
+                line: 3,
+                text: "-5".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_elves_overflow() {
+        assert_eq!(
+            parse_elves(["4294967295", "", "4294967295", "1"]).unwrap_err(),
+            ParseElvesError::Overflow { line: 4 }
+        );
+        assert_eq!(
+            parse_elves(["7", "99999999999"]).unwrap_err(),
+            ParseElvesError::Overflow { line: 2 }
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let inventory = inventory(&[&[1, 2], &[10], &[4], &[6]]);
+        assert_eq!(
+            inventory.stats(),
+            Some(Stats {
+                count: 4,
+                total: 23,
+                mean: 5.75,
+                median: 5.0,
+            })
+        );
+        assert_eq!(self::inventory(&[]).stats(), None);
+    }
+
+    #[test]
+    fn test_generate() {
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let input = generate(50, &mut rng).unwrap();
+        let elves = parse_elves(trimmed_lines(&input)).unwrap();
+        assert_eq!(elves.len(), 50);
+        assert!(elves
+            .iter()
+            .all(|elf| (1..=15).contains(&elf.snacks().len())));
+        assert_eq!(generate(50, &mut StdRng::seed_from_u64(1)).unwrap(), input);
+        assert!(generate(0, &mut rng).is_err());
+    }
 }
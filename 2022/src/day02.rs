@@ -1,8 +1,19 @@
-use std::{collections::HashMap, hash::Hash};
+mod decoding;
+mod game;
+mod tournament;
+
+use std::hash::Hash;
 
 use lazy_static::lazy_static;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use decoding::{Decoding, UnknownSymbolError};
+use game::CyclicGame;
+use tournament::{Standings, Strategy};
 
-use crate::file_wrappers::get_lines_from_embedded_file;
+use crate::file_wrappers::{get_embedded_text, lines, trimmed_lines};
+use crate::generate::check_size;
 
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
 enum RPSMove {
@@ -19,135 +30,223 @@ enum RPSResult {
 }
 
 lazy_static! {
-    static ref RPS_WIN_LOSS_MAP: HashMap<(RPSMove, RPSMove), RPSResult> = {
-        let mut m = HashMap::new();
-        m.insert((RPSMove::Rock, RPSMove::Rock), RPSResult::Draw);
-        m.insert((RPSMove::Rock, RPSMove::Paper), RPSResult::Loss);
-        m.insert((RPSMove::Rock, RPSMove::Scissors), RPSResult::Win);
-        m.insert((RPSMove::Paper, RPSMove::Rock), RPSResult::Win);
-        m.insert((RPSMove::Paper, RPSMove::Paper), RPSResult::Draw);
-        m.insert((RPSMove::Paper, RPSMove::Scissors), RPSResult::Loss);
-        m.insert((RPSMove::Scissors, RPSMove::Rock), RPSResult::Loss);
-        m.insert((RPSMove::Scissors, RPSMove::Paper), RPSResult::Win);
-        m.insert((RPSMove::Scissors, RPSMove::Scissors), RPSResult::Draw);
-        m
-    };
+    static ref ROCK_PAPER_SCISSORS: CyclicGame =
+        CyclicGame::standard(3).expect("Rock-paper-scissors is a valid cyclic game.");
+}
+
+impl RPSMove {
+    /// The position of the move in the rock-paper-scissors cycle.
+    fn index(self) -> usize {
+        match self {
+            RPSMove::Rock => 0,
+            RPSMove::Paper => 1,
+            RPSMove::Scissors => 2,
+        }
+    }
+
+    fn from_index(index: usize) -> RPSMove {
+        [RPSMove::Rock, RPSMove::Paper, RPSMove::Scissors][index]
+    }
+}
+
+impl std::fmt::Display for RPSMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            RPSMove::Rock => "rock",
+            RPSMove::Paper => "paper",
+            RPSMove::Scissors => "scissors",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::fmt::Display for RPSResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            RPSResult::Win => "win",
+            RPSResult::Draw => "draw",
+            RPSResult::Loss => "loss",
+        };
+        write!(f, "{}", s)
+    }
 }
 
 fn score(own_move: RPSMove, opponent_move: RPSMove) -> u32 {
-    let win_points = match RPS_WIN_LOSS_MAP.get(&(own_move, opponent_move)).unwrap() {
-        RPSResult::Win => 6u32,
-        RPSResult::Draw => 3u32,
-        RPSResult::Loss => 0u32,
-    };
+    ROCK_PAPER_SCISSORS.score(own_move.index(), opponent_move.index())
+}
 
-    let move_points = match own_move {
-        RPSMove::Rock => 1u32,
-        RPSMove::Paper => 2u32,
-        RPSMove::Scissors => 3u32,
-    };
+#[derive(Debug, PartialEq)]
+pub struct ParseStrategyGuideError {
+    line: usize,
+    text: String,
+}
 
-    win_points + move_points
+impl std::error::Error for ParseStrategyGuideError {}
+
+impl std::fmt::Display for ParseStrategyGuideError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: expected `<opponent> <response>`, found `{}`",
+            self.line, self.text
+        )
+    }
 }
 
-fn get_strategy_guide() -> anyhow::Result<Vec<(char, char)>> {
-    let lines = get_lines_from_embedded_file("input_day02.txt")?;
-    let chars: Vec<_> = lines
-        .iter()
-        .filter_map(|s| {
-            if let [a, b, ..] = s.split_whitespace().take(2).collect::<Vec<_>>().as_slice() {
-                Some((a.chars().next().unwrap(), b.chars().next().unwrap()))
-            } else {
-                None
+/// Parses one round per line as two single-character symbols, skipping blank lines. Line
+/// numbers in errors are 1-based.
+fn parse_strategy_guide<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<(char, char)>, ParseStrategyGuideError> {
+    let single = |token: &str| {
+        let mut chars = token.chars();
+        chars.next().filter(|_| chars.next().is_none())
+    };
+    lines
+        .into_iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let tokens: Vec<_> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                [a, b] => single(a).zip(single(b)),
+                _ => None,
             }
+            .ok_or_else(|| ParseStrategyGuideError {
+                line: i + 1,
+                text: line.to_string(),
+            })
         })
-        .collect();
+        .collect()
+}
+
+fn get_strategy_guide() -> anyhow::Result<Vec<(char, char)>> {
+    let input = get_embedded_text("input_day02.txt")?;
+    Ok(parse_strategy_guide(input.trimmed_lines())?)
+}
 
-    Ok(chars)
+fn get_default_decoding() -> anyhow::Result<Decoding> {
+    let input = get_embedded_text("day02_decoding.txt")?;
+    Ok(Decoding::parse(input.lines())?)
 }
 
-fn get_moves() -> anyhow::Result<Vec<(RPSMove, RPSMove)>> {
+fn get_moves(decoding: &Decoding) -> anyhow::Result<Vec<(RPSMove, RPSMove)>> {
     let strategy_guide = get_strategy_guide()?;
-    let moves: Vec<_> = strategy_guide
+    let moves = strategy_guide
         .iter()
-        .map(|(opponent, own)| {
-            (
-                match opponent {
-                    'A' => RPSMove::Rock,
-                    'B' => RPSMove::Paper,
-                    'C' => RPSMove::Scissors,
-                    c => panic!("Unrecognized move {}", c),
-                },
-                match own {
-                    'X' => RPSMove::Rock,
-                    'Y' => RPSMove::Paper,
-                    'Z' => RPSMove::Scissors,
-                    c => panic!("Unrecognized move {}", c),
-                },
-            )
-        })
-        .collect();
-
+        .map(|&(opponent, own)| Ok((decoding.opponent(opponent)?, decoding.own_move(own)?)))
+        .collect::<Result<Vec<_>, UnknownSymbolError>>()?;
     Ok(moves)
 }
 
-fn get_move_and_result() -> anyhow::Result<Vec<(RPSMove, RPSResult)>> {
+fn get_move_and_result(decoding: &Decoding) -> anyhow::Result<Vec<(RPSMove, RPSResult)>> {
     let strategy_guide = get_strategy_guide()?;
-    let moves: Vec<_> = strategy_guide
+    let moves = strategy_guide
         .iter()
-        .map(|(opponent, result)| {
-            (
-                match opponent {
-                    'A' => RPSMove::Rock,
-                    'B' => RPSMove::Paper,
-                    'C' => RPSMove::Scissors,
-                    c => panic!("Unrecognized move {}", c),
-                },
-                match result {
-                    'X' => RPSResult::Loss,
-                    'Y' => RPSResult::Draw,
-                    'Z' => RPSResult::Win,
-                    c => panic!("Unrecognized result {}", c),
-                },
-            )
-        })
-        .collect();
-
+        .map(|&(opponent, result)| Ok((decoding.opponent(opponent)?, decoding.outcome(result)?)))
+        .collect::<Result<Vec<_>, UnknownSymbolError>>()?;
     Ok(moves)
 }
 
-pub fn problem1() -> anyhow::Result<u32> {
-    let moves = get_moves()?;
-    let score = moves
+fn scores_for(decoding: &Decoding) -> anyhow::Result<(u32, u32)> {
+    let moves_score = get_moves(decoding)?
         .iter()
         .map(|(opponent, own)| score(*own, *opponent))
         .sum();
-    Ok(score)
+    let outcomes_score = get_move_and_result(decoding)?
+        .iter()
+        .map(|(opponent, result)| {
+            let own = RPSMove::from_index(ROCK_PAPER_SCISSORS.response(opponent.index(), *result));
+            score(own, *opponent)
+        })
+        .sum();
+    Ok((moves_score, outcomes_score))
 }
 
-pub fn problem2() -> anyhow::Result<u32> {
-    let mut necessary_move_map = HashMap::<(RPSMove, RPSResult), RPSMove>::new();
-    let possible_moves = [RPSMove::Rock, RPSMove::Paper, RPSMove::Scissors];
-    for own in possible_moves {
-        for opponent in possible_moves {
-            let result = RPS_WIN_LOSS_MAP.get(&(own, opponent)).unwrap();
-            necessary_move_map.insert((opponent, *result), own);
-        }
+fn read_decoding(decoding: Option<&str>) -> anyhow::Result<Decoding> {
+    match decoding {
+        Some(text) => Ok(Decoding::parse(text.lines())?),
+        None => get_default_decoding(),
     }
+}
+
+/// The total scores when the second column of the guide is read as moves and as outcomes,
+/// using the given decoding or the puzzle's one.
+pub fn scores(decoding: Option<&str>) -> anyhow::Result<(u32, u32)> {
+    scores_for(&read_decoding(decoding)?)
+}
 
-    let moves = get_move_and_result()?;
-    let score = moves
+/// The total scores, as for [`scores`], for every way of permuting the meaning of the
+/// second column's symbols, labelled with how each symbol is read.
+pub fn permutation_scores(decoding: Option<&str>) -> anyhow::Result<Vec<(String, u32, u32)>> {
+    read_decoding(decoding)?
+        .permutations()?
         .iter()
-        .map(|(opponent, result)| {
-            let own = necessary_move_map.get(&(*opponent, *result)).unwrap();
-            score(*own, *opponent)
+        .map(|decoding| {
+            let (moves_score, outcomes_score) = scores_for(decoding)?;
+            Ok((decoding.to_string(), moves_score, outcomes_score))
         })
-        .sum();
+        .collect()
+}
+
+/// Plays a round-robin tournament of `rounds` rounds per match between a fixed line-up of
+/// strategies, using `seed` for the random one.
+pub fn tournament(rounds: usize, seed: u64) -> anyhow::Result<Standings> {
+    let guide_moves = get_moves(&get_default_decoding()?)?
+        .into_iter()
+        .map(|(_, own)| own)
+        .collect();
+    let mut strategies: Vec<Box<dyn Strategy>> = vec![
+        Box::new(tournament::Fixed(RPSMove::Rock)),
+        Box::new(tournament::Fixed(RPSMove::Paper)),
+        Box::new(tournament::Fixed(RPSMove::Scissors)),
+        Box::new(tournament::Cyclic),
+        Box::new(tournament::FrequencyCounter),
+        Box::new(tournament::Random::new(seed)),
+        Box::new(tournament::ReplayGuide::new(guide_moves)),
+    ];
+    Ok(tournament::round_robin(&mut strategies, rounds))
+}
+
+pub fn problem1() -> anyhow::Result<u32> {
+    let (score, _) = scores(None)?;
+    Ok(score)
+}
+
+pub fn problem2() -> anyhow::Result<u32> {
+    let (_, score) = scores(None)?;
     Ok(score)
 }
 
+/// A random strategy guide of `size` rounds.
+pub fn generate(size: usize, rng: &mut StdRng) -> anyhow::Result<String> {
+    check_size(size, 1..=100_000)?;
+
+    let rounds: String = (0..size)
+        .map(|_| {
+            let opponent = ['A', 'B', 'C'].choose(rng).unwrap();
+            let own = ['X', 'Y', 'Z'].choose(rng).unwrap();
+            format!("{} {}\n", opponent, own)
+        })
+        .collect();
+    Ok(rounds)
+}
+
+/// Checks that `text` parses as a strategy guide.
+pub(crate) fn check_input(text: &str) -> anyhow::Result<()> {
+    parse_strategy_guide(trimmed_lines(text))?;
+    Ok(())
+}
+
+/// Checks that `text` parses as a decoding of the strategy guide's symbols.
+pub(crate) fn check_decoding(text: &str) -> anyhow::Result<()> {
+    Decoding::parse(lines(text))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::game::OutcomePoints;
     use super::*;
 
     #[test]
@@ -159,4 +258,184 @@ mod tests {
     fn test_problem2() {
         assert_eq!(problem2().unwrap(), 14416);
     }
+
+    #[test]
+    fn test_parse_strategy_guide() {
+        assert_eq!(
+            parse_strategy_guide(["A Y", "", "B X"]).unwrap(),
+            vec![('A', 'Y'), ('B', 'X')]
+        );
+        for (line, text) in [(2, "B"), (2, "B XY"), (2, "B X C"), (2, "é Z Z")] {
+            assert_eq!(
+                parse_strategy_guide(["A Y", text]).unwrap_err(),
+                ParseStrategyGuideError {
+                    line,
+                    text: text.to_string()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_decoding() {
+        let decoding = Decoding::parse(
+            "# Comment\n\nopponent A paper\nmove X scissors\noutcome X win".lines(),
+        )
+        .unwrap();
+        assert_eq!(decoding.opponent('A'), Ok(RPSMove::Paper));
+        assert_eq!(decoding.own_move('X'), Ok(RPSMove::Scissors));
+        assert_eq!(decoding.outcome('X'), Ok(RPSResult::Win));
+        assert!(decoding.opponent('B').is_err());
+        assert_eq!(decoding.to_string(), "X=scissors/win");
+    }
+
+    #[test]
+    fn test_invalid_decodings() {
+        assert!(Decoding::parse(["opponent A lizard"]).is_err());
+        assert!(Decoding::parse(["outcome X rock"]).is_err());
+        assert!(Decoding::parse(["player X rock"]).is_err());
+        assert!(Decoding::parse(["move XY rock"]).is_err());
+        assert!(Decoding::parse(["move X rock", "move X paper"]).is_err());
+    }
+
+    #[test]
+    fn test_permutation_scores() {
+        let scores = permutation_scores(None).unwrap();
+        assert_eq!(scores.len(), 6);
+        assert!(scores.contains(&(
+            "X=rock/loss Y=paper/draw Z=scissors/win".to_string(),
+            15632,
+            14416
+        )));
+    }
+
+    #[test]
+    fn test_permutations_need_three_symbols() {
+        let two = Decoding::parse(["move X rock", "outcome Y win"]).unwrap();
+        assert_eq!(
+            two.permutations().unwrap_err().to_string(),
+            "The second column must have exactly 3 symbols to permute, found 2."
+        );
+        let four =
+            Decoding::parse(["move W rock", "move X rock", "move Y rock", "move Z rock"]).unwrap();
+        assert!(four.permutations().is_err());
+        assert!(permutation_scores(Some("move X rock\noutcome X win\n")).is_err());
+    }
+
+    #[test]
+    fn test_fixed_strategies() {
+        let mut strategies: Vec<Box<dyn Strategy>> = vec![
+            Box::new(tournament::Fixed(RPSMove::Rock)),
+            Box::new(tournament::Fixed(RPSMove::Paper)),
+        ];
+        let standings = tournament::round_robin(&mut strategies, 10);
+        assert_eq!(
+            standings.rows[0],
+            tournament::Standing {
+                name: "always paper".to_string(),
+                score: 80,
+                wins: 1,
+                draws: 0,
+                losses: 0,
+            }
+        );
+        assert_eq!(standings.rows[1].score, 10);
+    }
+
+    #[test]
+    fn test_frequency_counter_beats_fixed_strategies() {
+        for m in [RPSMove::Rock, RPSMove::Paper, RPSMove::Scissors] {
+            let mut strategies: Vec<Box<dyn Strategy>> = vec![
+                Box::new(tournament::Fixed(m)),
+                Box::new(tournament::FrequencyCounter),
+            ];
+            let standings = tournament::round_robin(&mut strategies, 100);
+            assert_eq!(standings.rows[0].name, "frequency counter");
+        }
+    }
+
+    #[test]
+    fn test_tournament_is_reproducible() {
+        let first = tournament(50, 7).unwrap();
+        let second = tournament(50, 7).unwrap();
+        assert_eq!(first.rows, second.rows);
+        assert_eq!(first.rows.len(), 7);
+        let matches: u32 = first.rows.iter().map(|r| r.wins + r.draws + r.losses).sum();
+        assert_eq!(matches, 7 * 6);
+    }
+
+    #[test]
+    fn test_generate() {
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let input = generate(50, &mut rng).unwrap();
+        let guide = parse_strategy_guide(lines(&input)).unwrap();
+        assert_eq!(guide.len(), 50);
+        let decoding = get_default_decoding().unwrap();
+        for (opponent, own) in guide {
+            assert!(decoding.opponent(opponent).is_ok());
+            assert!(decoding.own_move(own).is_ok());
+        }
+        assert!(generate(0, &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_rock_paper_scissors() {
+        let rps = &*ROCK_PAPER_SCISSORS;
+        assert_eq!(
+            rps.result(RPSMove::Paper.index(), RPSMove::Rock.index()),
+            RPSResult::Win
+        );
+        assert_eq!(
+            rps.result(RPSMove::Rock.index(), RPSMove::Scissors.index()),
+            RPSResult::Win
+        );
+        assert_eq!(
+            rps.result(RPSMove::Rock.index(), RPSMove::Paper.index()),
+            RPSResult::Loss
+        );
+        assert_eq!(score(RPSMove::Paper, RPSMove::Rock), 8);
+        assert_eq!(score(RPSMove::Rock, RPSMove::Paper), 1);
+        assert_eq!(score(RPSMove::Scissors, RPSMove::Scissors), 6);
+    }
+
+    #[test]
+    fn test_cyclic_games() {
+        for size in [3, 5, 7, 9] {
+            let game = CyclicGame::standard(size).unwrap();
+            for own in 0..size {
+                let wins = (0..size)
+                    .filter(|&opponent| game.result(own, opponent) == RPSResult::Win)
+                    .count();
+                assert_eq!(wins, size / 2);
+                for opponent in 0..size {
+                    let result = game.result(own, opponent);
+                    assert_eq!(
+                        game.result(game.response(opponent, result), opponent),
+                        result
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rock_paper_scissors_lizard_spock() {
+        // Rock, Spock, paper, lizard, scissors.
+        let game = CyclicGame::standard(5).unwrap();
+        assert_eq!(game.result(0, 3), RPSResult::Win); // Rock crushes lizard.
+        assert_eq!(game.result(0, 4), RPSResult::Win); // Rock crushes scissors.
+        assert_eq!(game.result(1, 0), RPSResult::Win); // Spock vaporizes rock.
+        assert_eq!(game.result(3, 1), RPSResult::Win); // Lizard poisons Spock.
+        assert_eq!(game.result(2, 1), RPSResult::Win); // Paper disproves Spock.
+        assert_eq!(game.score(3, 1), 10);
+    }
+
+    #[test]
+    fn test_invalid_games() {
+        assert!(CyclicGame::standard(4).is_err());
+        assert!(CyclicGame::standard(1).is_err());
+        assert!(CyclicGame::new(vec![], OutcomePoints::default()).is_err());
+    }
 }
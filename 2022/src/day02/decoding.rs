@@ -0,0 +1,222 @@
+//! Reading the symbols of the strategy guide as moves and outcomes.
+//!
+//! The mapping is data rather than code. Each non-blank line of a decoding which isn't a `#`
+//! comment is one of
+//!
+//! ```text
+//! opponent <symbol> rock|paper|scissors
+//! move <symbol> rock|paper|scissors
+//! outcome <symbol> win|draw|loss
+//! ```
+
+use std::collections::HashMap;
+
+use super::{RPSMove, RPSResult};
+
+#[derive(Debug, PartialEq)]
+pub struct DecodingError {
+    line: usize,
+    message: String,
+}
+
+impl std::error::Error for DecodingError {}
+
+impl std::fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "decoding line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UnknownSymbolError {
+    column: &'static str,
+    symbol: char,
+}
+
+impl std::error::Error for UnknownSymbolError {}
+
+impl std::fmt::Display for UnknownSymbolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "The decoding has no {} for `{}`.",
+            self.column, self.symbol
+        )
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SymbolCountError {
+    count: usize,
+}
+
+impl std::error::Error for SymbolCountError {}
+
+impl std::fmt::Display for SymbolCountError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "The second column must have exactly 3 symbols to permute, found {}.",
+            self.count
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Decoding {
+    opponent: HashMap<char, RPSMove>,
+    own_move: HashMap<char, RPSMove>,
+    outcome: HashMap<char, RPSResult>,
+}
+
+impl Decoding {
+    pub fn parse<'a>(lines: impl IntoIterator<Item = &'a str>) -> Result<Decoding, DecodingError> {
+        let mut decoding = Decoding::default();
+        for (i, line) in lines.into_iter().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| DecodingError {
+                line: i + 1,
+                message,
+            };
+
+            let tokens: Vec<_> = line.split_whitespace().collect();
+            let (column, symbol, value) = match tokens.as_slice() {
+                [column, symbol, value] if symbol.chars().count() == 1 => {
+                    (*column, symbol.chars().next().unwrap(), *value)
+                }
+                _ => return Err(error("expected `<column> <symbol> <value>`".to_string())),
+            };
+            let previous = match column {
+                "opponent" => decoding
+                    .opponent
+                    .insert(symbol, parse_move(value).map_err(error)?)
+                    .is_some(),
+                "move" => decoding
+                    .own_move
+                    .insert(symbol, parse_move(value).map_err(error)?)
+                    .is_some(),
+                "outcome" => decoding
+                    .outcome
+                    .insert(symbol, parse_outcome(value).map_err(error)?)
+                    .is_some(),
+                _ => return Err(error(format!("unknown column `{}`", column))),
+            };
+            if previous {
+                return Err(error(format!("`{}` is already a {}", symbol, column)));
+            }
+        }
+        Ok(decoding)
+    }
+
+    pub fn opponent(&self, symbol: char) -> Result<RPSMove, UnknownSymbolError> {
+        decode(&self.opponent, "opponent move", symbol)
+    }
+
+    pub fn own_move(&self, symbol: char) -> Result<RPSMove, UnknownSymbolError> {
+        decode(&self.own_move, "move", symbol)
+    }
+
+    pub fn outcome(&self, symbol: char) -> Result<RPSResult, UnknownSymbolError> {
+        decode(&self.outcome, "outcome", symbol)
+    }
+
+    /// Every decoding which reads the second column's symbols as some permutation of the
+    /// moves, and the same permutation of the outcomes, keeping the opponent column as is.
+    /// There must be exactly one symbol per move.
+    pub fn permutations(&self) -> Result<Vec<Decoding>, SymbolCountError> {
+        let mut symbols: Vec<_> = self.own_move.keys().chain(self.outcome.keys()).collect();
+        symbols.sort_unstable();
+        symbols.dedup();
+        if symbols.len() != 3 {
+            return Err(SymbolCountError {
+                count: symbols.len(),
+            });
+        }
+
+        let moves = [RPSMove::Rock, RPSMove::Paper, RPSMove::Scissors];
+        let outcomes = [RPSResult::Loss, RPSResult::Draw, RPSResult::Win];
+        Ok(permutations(&[0, 1, 2])
+            .into_iter()
+            .map(|permutation| Decoding {
+                opponent: self.opponent.clone(),
+                own_move: symbols
+                    .iter()
+                    .zip(&permutation)
+                    .map(|(&&symbol, &i)| (symbol, moves[i]))
+                    .collect(),
+                outcome: symbols
+                    .iter()
+                    .zip(&permutation)
+                    .map(|(&&symbol, &i)| (symbol, outcomes[i]))
+                    .collect(),
+            })
+            .collect())
+    }
+}
+
+impl std::fmt::Display for Decoding {
+    /// Shows how the second column is read, e.g. `X=rock/loss Y=paper/draw Z=scissors/win`.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut symbols: Vec<_> = self.own_move.keys().chain(self.outcome.keys()).collect();
+        symbols.sort_unstable();
+        symbols.dedup();
+
+        let columns: Vec<_> = symbols
+            .iter()
+            .map(|symbol| {
+                let own_move = self.own_move.get(symbol).map(ToString::to_string);
+                let outcome = self.outcome.get(symbol).map(ToString::to_string);
+                let value = [own_move, outcome].into_iter().flatten();
+                format!("{}={}", symbol, value.collect::<Vec<_>>().join("/"))
+            })
+            .collect();
+        write!(f, "{}", columns.join(" "))
+    }
+}
+
+fn parse_move(s: &str) -> Result<RPSMove, String> {
+    match s {
+        "rock" => Ok(RPSMove::Rock),
+        "paper" => Ok(RPSMove::Paper),
+        "scissors" => Ok(RPSMove::Scissors),
+        _ => Err(format!("unknown move `{}`", s)),
+    }
+}
+
+fn parse_outcome(s: &str) -> Result<RPSResult, String> {
+    match s {
+        "win" => Ok(RPSResult::Win),
+        "draw" => Ok(RPSResult::Draw),
+        "loss" => Ok(RPSResult::Loss),
+        _ => Err(format!("unknown outcome `{}`", s)),
+    }
+}
+
+fn decode<T: Copy>(
+    map: &HashMap<char, T>,
+    column: &'static str,
+    symbol: char,
+) -> Result<T, UnknownSymbolError> {
+    map.get(&symbol)
+        .copied()
+        .ok_or(UnknownSymbolError { column, symbol })
+}
+
+fn permutations(items: &[usize]) -> Vec<Vec<usize>> {
+    if items.is_empty() {
+        return vec![vec![]];
+    }
+    (0..items.len())
+        .flat_map(|i| {
+            let mut rest = items.to_vec();
+            let first = rest.remove(i);
+            permutations(&rest).into_iter().map(move |mut permutation| {
+                permutation.insert(0, first);
+                permutation
+            })
+        })
+        .collect()
+}
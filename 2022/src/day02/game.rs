@@ -0,0 +1,127 @@
+//! Cyclic games such as rock-paper-scissors, generalised to any odd number of moves.
+//!
+//! Moves are numbered `0..n` around the cycle, and each move beats the `(n - 1) / 2` moves
+//! before it and loses to the `(n - 1) / 2` moves after it, so every pair of distinct moves
+//! has exactly one winner. With three moves numbered rock, paper, scissors this is the usual
+//! game; with five numbered rock, Spock, paper, lizard, scissors it's
+//! rock-paper-scissors-lizard-Spock.
+
+use super::RPSResult;
+
+#[derive(Debug, PartialEq)]
+pub struct InvalidGameError {
+    size: usize,
+}
+
+impl std::error::Error for InvalidGameError {}
+
+impl std::fmt::Display for InvalidGameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "A cyclic game needs an odd number of moves, at least three, but {} were given.",
+            self.size
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutcomePoints {
+    pub win: u32,
+    pub draw: u32,
+    pub loss: u32,
+}
+
+impl Default for OutcomePoints {
+    fn default() -> Self {
+        Self {
+            win: 6,
+            draw: 3,
+            loss: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CyclicGame {
+    move_points: Vec<u32>,
+    outcome_points: OutcomePoints,
+    /// The result for the first player, indexed by both players' moves.
+    results: Vec<Vec<RPSResult>>,
+    /// The move which achieves a result, indexed by the opponent's move and the result.
+    responses: Vec<[usize; 3]>,
+}
+
+impl CyclicGame {
+    /// A game with one move per entry of `move_points`, scoring that many points for playing
+    /// it on top of the points for the outcome.
+    pub fn new(
+        move_points: Vec<u32>,
+        outcome_points: OutcomePoints,
+    ) -> Result<CyclicGame, InvalidGameError> {
+        let size = move_points.len();
+        if size < 3 || size % 2 == 0 {
+            return Err(InvalidGameError { size });
+        }
+
+        let results: Vec<Vec<_>> = (0..size)
+            .map(|own| {
+                (0..size)
+                    .map(|opponent| match (own + size - opponent) % size {
+                        0 => RPSResult::Draw,
+                        d if d <= size / 2 => RPSResult::Win,
+                        _ => RPSResult::Loss,
+                    })
+                    .collect()
+            })
+            .collect();
+        let responses = (0..size)
+            .map(|opponent| {
+                let mut response = [0; 3];
+                for own in (0..size).rev() {
+                    response[Self::result_index(results[own][opponent])] = own;
+                }
+                response
+            })
+            .collect();
+
+        Ok(CyclicGame {
+            move_points,
+            outcome_points,
+            results,
+            responses,
+        })
+    }
+
+    /// A game of `size` moves scoring 1 to `size` points for the moves and 6, 3 and 0 for a
+    /// win, draw and loss.
+    pub fn standard(size: usize) -> Result<CyclicGame, InvalidGameError> {
+        CyclicGame::new((1..=size as u32).collect(), OutcomePoints::default())
+    }
+
+    pub fn result(&self, own: usize, opponent: usize) -> RPSResult {
+        self.results[own][opponent]
+    }
+
+    /// The first move, in cycle order, which achieves `result` against `opponent`.
+    pub fn response(&self, opponent: usize, result: RPSResult) -> usize {
+        self.responses[opponent][Self::result_index(result)]
+    }
+
+    pub fn score(&self, own: usize, opponent: usize) -> u32 {
+        let outcome_points = match self.result(own, opponent) {
+            RPSResult::Win => self.outcome_points.win,
+            RPSResult::Draw => self.outcome_points.draw,
+            RPSResult::Loss => self.outcome_points.loss,
+        };
+        outcome_points + self.move_points[own]
+    }
+
+    fn result_index(result: RPSResult) -> usize {
+        match result {
+            RPSResult::Win => 0,
+            RPSResult::Draw => 1,
+            RPSResult::Loss => 2,
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Round-robin tournaments between rock-paper-scissors strategies.
+//!
+//! Every strategy plays every other strategy once, for a fixed number of rounds, and is reset
+//! before each match. Rounds are scored with the puzzle's rules, so a strategy's total is the
+//! sum of its scores over all rounds it played.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::{score, RPSMove, RPSResult, ROCK_PAPER_SCISSORS};
+
+const MOVES: [RPSMove; 3] = [RPSMove::Rock, RPSMove::Paper, RPSMove::Scissors];
+
+pub trait Strategy {
+    fn name(&self) -> String;
+
+    /// Prepares for a new match against a new opponent.
+    fn reset(&mut self) {}
+
+    /// Chooses the next move, knowing every move the opponent made so far in this match.
+    fn next_move(&mut self, opponent_history: &[RPSMove]) -> RPSMove;
+}
+
+/// Always plays the same move.
+pub struct Fixed(pub RPSMove);
+
+impl Strategy for Fixed {
+    fn name(&self) -> String {
+        format!("always {}", self.0)
+    }
+
+    fn next_move(&mut self, _: &[RPSMove]) -> RPSMove {
+        self.0
+    }
+}
+
+/// Plays rock, paper, scissors, rock, and so on.
+pub struct Cyclic;
+
+impl Strategy for Cyclic {
+    fn name(&self) -> String {
+        "cyclic".to_string()
+    }
+
+    fn next_move(&mut self, opponent_history: &[RPSMove]) -> RPSMove {
+        MOVES[opponent_history.len() % MOVES.len()]
+    }
+}
+
+/// Plays whatever beats the opponent's most frequent move so far, preferring rock, then
+/// paper, then scissors on a tie.
+pub struct FrequencyCounter;
+
+impl Strategy for FrequencyCounter {
+    fn name(&self) -> String {
+        "frequency counter".to_string()
+    }
+
+    fn next_move(&mut self, opponent_history: &[RPSMove]) -> RPSMove {
+        let mut counts = [0; 3];
+        for m in opponent_history {
+            counts[m.index()] += 1;
+        }
+        let most_frequent = (0..counts.len())
+            .rev()
+            .max_by_key(|&i| counts[i])
+            .expect("There are three moves.");
+        RPSMove::from_index(ROCK_PAPER_SCISSORS.response(most_frequent, RPSResult::Win))
+    }
+}
+
+/// Plays uniformly random moves, repeating the same sequence in every match.
+pub struct Random {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for Random {
+    fn name(&self) -> String {
+        format!("random ({})", self.seed)
+    }
+
+    fn reset(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+
+    fn next_move(&mut self, _: &[RPSMove]) -> RPSMove {
+        MOVES[self.rng.gen_range(0..MOVES.len())]
+    }
+}
+
+/// Plays the moves of the strategy guide in order, starting over when it runs out.
+pub struct ReplayGuide {
+    moves: Vec<RPSMove>,
+}
+
+impl ReplayGuide {
+    pub fn new(moves: Vec<RPSMove>) -> ReplayGuide {
+        ReplayGuide { moves }
+    }
+}
+
+impl Strategy for ReplayGuide {
+    fn name(&self) -> String {
+        "replay the guide".to_string()
+    }
+
+    fn next_move(&mut self, opponent_history: &[RPSMove]) -> RPSMove {
+        if self.moves.is_empty() {
+            return RPSMove::Rock;
+        }
+        self.moves[opponent_history.len() % self.moves.len()]
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub score: u64,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// The result of a tournament, best total score first, with wins, draws and losses counted
+/// per match.
+#[derive(Debug)]
+pub struct Standings {
+    pub rows: Vec<Standing>,
+}
+
+impl std::fmt::Display for Standings {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let width = self
+            .rows
+            .iter()
+            .map(|row| row.name.len())
+            .chain(Some("strategy".len()))
+            .max()
+            .unwrap_or(0);
+        writeln!(
+            f,
+            "{:>4}  {:<width$}  {:>8}  {:>4}  {:>5}  {:>6}",
+            "rank",
+            "strategy",
+            "score",
+            "wins",
+            "draws",
+            "losses",
+            width = width
+        )?;
+        for (i, row) in self.rows.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4}  {:<width$}  {:>8}  {:>4}  {:>5}  {:>6}",
+                i + 1,
+                row.name,
+                row.score,
+                row.wins,
+                row.draws,
+                row.losses,
+                width = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plays one match of `rounds` rounds and returns both players' scores. A round scores at
+/// most 9, so a `u64` total can't overflow for any number of rounds which fits in memory.
+fn play_match(a: &mut dyn Strategy, b: &mut dyn Strategy, rounds: usize) -> (u64, u64) {
+    a.reset();
+    b.reset();
+
+    let mut a_history = Vec::with_capacity(rounds);
+    let mut b_history = Vec::with_capacity(rounds);
+    let (mut a_score, mut b_score) = (0, 0);
+    for _ in 0..rounds {
+        let a_move = a.next_move(&b_history);
+        let b_move = b.next_move(&a_history);
+        a_score += u64::from(score(a_move, b_move));
+        b_score += u64::from(score(b_move, a_move));
+        a_history.push(a_move);
+        b_history.push(b_move);
+    }
+    (a_score, b_score)
+}
+
+pub fn round_robin(strategies: &mut [Box<dyn Strategy>], rounds: usize) -> Standings {
+    let mut rows: Vec<_> = strategies
+        .iter()
+        .map(|strategy| Standing {
+            name: strategy.name(),
+            ..Standing::default()
+        })
+        .collect();
+
+    for i in 0..strategies.len() {
+        let (left, right) = strategies.split_at_mut(i + 1);
+        let a = &mut left[i];
+        for (j, b) in right.iter_mut().enumerate() {
+            let j = i + 1 + j;
+            let (a_score, b_score) = play_match(a.as_mut(), b.as_mut(), rounds);
+            rows[i].score += a_score;
+            rows[j].score += b_score;
+            match a_score.cmp(&b_score) {
+                std::cmp::Ordering::Greater => {
+                    rows[i].wins += 1;
+                    rows[j].losses += 1;
+                }
+                std::cmp::Ordering::Equal => {
+                    rows[i].draws += 1;
+                    rows[j].draws += 1;
+                }
+                std::cmp::Ordering::Less => {
+                    rows[i].losses += 1;
+                    rows[j].wins += 1;
+                }
+            }
+        }
+    }
+
+    rows.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    Standings { rows }
+}
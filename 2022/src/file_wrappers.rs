@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use anyhow::Context;
 
 use rust_embed::RustEmbed;
@@ -6,9 +8,84 @@ use rust_embed::RustEmbed;
 #[folder = "assets/"]
 struct Asset;
 
-pub fn get_lines_from_embedded_file(name: &str) -> anyhow::Result<Vec<String>> {
+/// The contents of an embedded file, checked to be UTF-8 once so that lines and other views
+/// can borrow from it rather than being copied.
+///
+/// In release builds the contents are borrowed from the binary itself; in debug builds they
+/// are read from disk once, when the file is loaded.
+pub struct EmbeddedText {
+    contents: Cow<'static, str>,
+}
+
+impl EmbeddedText {
+    /// The whole text, without a byte order mark at either end.
+    pub fn text(&self) -> &str {
+        strip_byte_order_mark(&self.contents)
+    }
+
+    /// The lines of the text, without their `\n` or `\r\n` endings.
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        lines(self.text())
+    }
+
+    /// The lines of the text, without their endings or any trailing whitespace.
+    pub fn trimmed_lines(&self) -> impl Iterator<Item = &str> {
+        trimmed_lines(self.text())
+    }
+}
+
+fn strip_byte_order_mark(text: &str) -> &str {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    text.strip_suffix('\u{feff}').unwrap_or(text)
+}
+
+/// Splits `text` into lines however it was saved, ignoring a byte order mark at either end
+/// and accepting both `\n` and `\r\n` line endings.
+pub fn lines(text: &str) -> impl Iterator<Item = &str> {
+    strip_byte_order_mark(text)
+        .lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+/// Like [`lines`], but also trims trailing whitespace from each line.
+pub fn trimmed_lines(text: &str) -> impl Iterator<Item = &str> {
+    lines(text).map(str::trim_end)
+}
+
+pub fn get_embedded_text(name: &str) -> anyhow::Result<EmbeddedText> {
     let file = Asset::get(name).with_context(|| name.to_string())?;
-    let contents = std::str::from_utf8(file.data.as_ref())?;
-    let lines = contents.lines();
-    Ok(lines.map(|s| s.to_string()).collect())
+    let contents = match file.data {
+        Cow::Borrowed(data) => Cow::Borrowed(std::str::from_utf8(data)?),
+        Cow::Owned(data) => Cow::Owned(String::from_utf8(data)?),
+    };
+    Ok(EmbeddedText { contents })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_assets() {
+        // Every embedded file reads back as the same lines after saving it as Notepad might,
+        // with byte order marks, `\r\n` line endings and trailing spaces.
+        for name in Asset::iter() {
+            let text = get_embedded_text(&name).unwrap();
+            let windows = format!("\u{feff}{}\u{feff}", text.text().replace('\n', "\r\n"));
+            assert_eq!(
+                lines(&windows).collect::<Vec<_>>(),
+                text.lines().collect::<Vec<_>>(),
+                "{}",
+                name
+            );
+
+            let padded = windows.replace("\r\n", " \t\r\n");
+            assert_eq!(
+                trimmed_lines(&padded).collect::<Vec<_>>(),
+                text.trimmed_lines().collect::<Vec<_>>(),
+                "{}",
+                name
+            );
+        }
+    }
 }
@@ -0,0 +1,144 @@
+//! Entry points for fuzzing the parsers, shared by the property tests below and the
+//! cargo-fuzz targets in `fuzz/`.
+//!
+//! Whatever the input, a parser must return either a value or an error: it must never
+//! panic, and it must never hang.
+
+use crate::{day01, day02};
+
+/// The kinds of input which can be parsed: each day's puzzle input, plus day 2's decoding
+/// of the strategy guide's symbols.
+pub const INPUTS: [&str; 3] = ["day01", "day02", "day02-decoding"];
+
+/// Parses `text` as the kind of input called `name`.
+pub fn parse(name: &str, text: &str) -> anyhow::Result<()> {
+    match name {
+        "day01" => day01::check_input(text),
+        "day02" => day02::check_input(text),
+        "day02-decoding" => day02::check_decoding(text),
+        _ => anyhow::bail!("There is no input called `{}`.", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::thread;
+    use std::time::Duration;
+
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseError;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::file_wrappers::get_embedded_text;
+
+    /// Far longer than any parser needs for the inputs below.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// A small random input of the kind called `name`, or `None` if there is no generator
+    /// for it.
+    fn generate(name: &str, seed: u64) -> Option<String> {
+        let rng = &mut StdRng::seed_from_u64(seed);
+        let input = match name {
+            "day01" => day01::generate(5, rng),
+            "day02" => day02::generate(10, rng),
+            _ => return None,
+        };
+        Some(input.unwrap())
+    }
+
+    /// Applies each `(position, operation, c)` edit to `text` in turn, inserting `c`,
+    /// replacing a character with `c`, or deleting a character.
+    fn mutate(text: &str, edits: &[(usize, u8, char)]) -> String {
+        let mut chars = text.chars().collect::<Vec<_>>();
+        for &(position, operation, c) in edits {
+            let position = position % (chars.len() + 1);
+            match (operation % 3, position < chars.len()) {
+                (0, _) => chars.insert(position, c),
+                (1, true) => chars[position] = c,
+                (2, true) => {
+                    chars.remove(position);
+                }
+                _ => {}
+            }
+        }
+        chars.into_iter().collect()
+    }
+
+    /// Parses `text` on another thread, failing if the parser panics or doesn't finish.
+    fn parse_promptly(name: &'static str, text: String) -> Result<(), TestCaseError> {
+        let (sender, receiver) = mpsc::channel();
+        let parser = thread::spawn(move || {
+            let _ = parse(name, &text);
+            let _ = sender.send(());
+        });
+        match receiver.recv_timeout(TIMEOUT) {
+            Ok(()) => Ok(()),
+            Err(RecvTimeoutError::Disconnected) => {
+                let panic = parser.join().unwrap_err();
+                let message = panic
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_default();
+                Err(TestCaseError::fail(format!("panicked: {}", message)))
+            }
+            Err(RecvTimeoutError::Timeout) => Err(TestCaseError::fail("timed out")),
+        }
+    }
+
+    /// Characters which mean something in at least one of the input formats.
+    const ALPHABET: &str = "[ABCXYZ#0-9a-z \t\r\n\u{feff}]{0,300}";
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(1000))]
+
+        #[test]
+        fn test_arbitrary_text(name in prop::sample::select(&INPUTS[..]), text in any::<String>()) {
+            parse_promptly(name, text)?;
+        }
+
+        #[test]
+        fn test_text_from_alphabet(name in prop::sample::select(&INPUTS[..]), text in ALPHABET) {
+            parse_promptly(name, text)?;
+        }
+
+        #[test]
+        fn test_mutated_inputs(
+            name in prop::sample::select(&INPUTS[..]),
+            seed in any::<u64>(),
+            edits in prop::collection::vec((any::<usize>(), any::<u8>(), any::<char>()), 1..8),
+        ) {
+            if let Some(text) = generate(name, seed) {
+                parse_promptly(name, mutate(&text, &edits))?;
+            }
+        }
+    }
+
+    #[test]
+    fn test_generated_inputs_parse() {
+        for name in &INPUTS {
+            for seed in 0..10 {
+                if let Some(text) = generate(name, seed) {
+                    if let Err(e) = parse(name, &text) {
+                        panic!("{} input {} failed to parse: {}\n{}", name, seed, e, text);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_puzzle_inputs_parse() {
+        for (name, file) in [
+            ("day01", "input_day01.txt"),
+            ("day02", "input_day02.txt"),
+            ("day02-decoding", "day02_decoding.txt"),
+        ] {
+            parse(name, get_embedded_text(file).unwrap().text()).unwrap();
+        }
+        assert!(parse("day03", "").is_err());
+    }
+}
@@ -1,3 +1,6 @@
 pub mod day01;
 pub mod day02;
 mod file_wrappers;
+pub mod fuzz;
+mod generate;
+mod records;
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use adventofcode::{day01, day02};
+use report::Format;
+
+const USAGE: &str = "usage: adventofcode [--format plain|json|table]
+       adventofcode day01 [--top <k>]
+       adventofcode day01 elf <index>
+       adventofcode day02 [--decoding <path>]
+       adventofcode day02 permutations [--decoding <path>]
+       adventofcode day02 tournament [--rounds <n>] [--seed <seed>]
+       adventofcode generate <day> [--size <n>] [--seed <seed>]";
+
+#[derive(Debug)]
+struct UsageError;
+
+impl std::error::Error for UsageError {}
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", USAGE)
+    }
+}
+
+fn parse_options<'a>(args: &[&'a str]) -> Result<HashMap<&'a str, &'a str>, UsageError> {
+    let mut options = HashMap::new();
+    for pair in args.chunks(2) {
+        match pair {
+            [name, value] if name.starts_with("--") => {
+                options.insert(&name[2..], *value);
+            }
+            _ => return Err(UsageError),
+        }
+    }
+    Ok(options)
+}
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    match args.as_slice() {
+        ["day01", "elf", index] => elf(index.parse()?),
+        ["day01", options @ ..] => {
+            let options = parse_options(options)?;
+            let k = options.get("top").map_or(Ok(3), |k| k.parse())?;
+            top_elves(k)
+        }
+        ["day02", "permutations", options @ ..] => {
+            let decoding = read_decoding(&parse_options(options)?)?;
+            for (decoding, moves_score, outcomes_score) in
+                day02::permutation_scores(decoding.as_deref())?
+            {
+                println!("{}: {} {}", decoding, moves_score, outcomes_score);
+            }
+            Ok(())
+        }
+        ["day02", "tournament", options @ ..] => {
+            let options = parse_options(options)?;
+            let rounds = options.get("rounds").map_or(Ok(1000), |n| n.parse())?;
+            let seed = options.get("seed").map_or(Ok(0), |seed| seed.parse())?;
+            print!("{}", day02::tournament(rounds, seed)?);
+            Ok(())
+        }
+        ["day02", options @ ..] => {
+            let decoding = read_decoding(&parse_options(options)?)?;
+            let (moves_score, outcomes_score) = day02::scores(decoding.as_deref())?;
+            println!("{} {}", moves_score, outcomes_score);
+            Ok(())
+        }
+        ["generate", day, options @ ..] => {
+            let options = parse_options(options)?;
+            let size = options.get("size").map(|n| n.parse()).transpose()?;
+            let seed = options.get("seed").map_or(Ok(0), |seed| seed.parse())?;
+            generate(day, size, seed)
+        }
+        options => {
+            let options = parse_options(options)?;
+            let format = options.get("format").map(|f| f.parse()).transpose()?;
+            run_all(format)
+        }
+    }
+}
+
+/// Prints a random input for `day`, of the given size or else one comparable to the
+/// puzzle's own input.
+fn generate(day: &str, size: Option<usize>, seed: u64) -> anyhow::Result<()> {
+    type Generator = fn(usize, &mut StdRng) -> anyhow::Result<String>;
+    let (generator, default_size): (Generator, usize) = match day {
+        "day01" => (day01::generate, 250),
+        "day02" => (day02::generate, 2500),
+        _ => return Err(UsageError.into()),
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    print!("{}", generator(size.unwrap_or(default_size), &mut rng)?);
+    Ok(())
+}
+
+/// Runs every solver, reporting each answer in `format` or else one line per day, and fails
+/// if any solver did.
+fn run_all(format: Option<Format>) -> anyhow::Result<()> {
+    let outcomes = [
+        report::run(2022, 1, 1, day01::problem1),
+        report::run(2022, 1, 2, day01::problem2),
+        report::run(2022, 2, 1, day02::problem1),
+        report::run(2022, 2, 2, day02::problem2),
+    ];
+    match format {
+        Some(format) => print!("{}", report::render(format, &outcomes)),
+        None => {
+            for day in outcomes.chunks(2) {
+                let answers: Vec<_> = day
+                    .iter()
+                    .filter_map(|outcome| outcome.answer.as_deref().ok())
+                    .collect();
+                if !answers.is_empty() {
+                    println!("day{:02}: {}", day[0].day, answers.join(" "));
+                }
+            }
+        }
+    }
+
+    let failures: Vec<_> = outcomes
+        .iter()
+        .filter_map(|outcome| Some((outcome, outcome.answer.as_ref().err()?)))
+        .collect();
+    if let Some((outcome, error)) = failures.first() {
+        anyhow::bail!(
+            "{} of {} solvers failed, first day {} part {}: {}",
+            failures.len(),
+            outcomes.len(),
+            outcome.day,
+            outcome.part,
+            error
+        );
+    }
+    Ok(())
+}
+
+/// The contents of the file given by `--decoding`, if any.
+fn read_decoding(options: &HashMap<&str, &str>) -> anyhow::Result<Option<String>> {
+    let decoding = options
+        .get("decoding")
+        .map(std::fs::read_to_string)
+        .transpose()?;
+    Ok(decoding)
+}
+
+fn top_elves(k: usize) -> anyhow::Result<()> {
+    let inventory = day01::get_inventory()?;
+    for (index, total) in inventory.top(k) {
+        println!("elf {}: {}", index, total);
+    }
+    if let Some(stats) = inventory.stats() {
+        println!(
+            "elves: {}, total: {}, mean: {:.1}, median: {:.1}",
+            stats.count, stats.total, stats.mean, stats.median
+        );
+    }
+    Ok(())
+}
+
+fn elf(index: usize) -> anyhow::Result<()> {
+    let inventory = day01::get_inventory()?;
+    match (inventory.elf(index), inventory.rank(index)) {
+        (Some(elf), Some(rank)) => {
+            let snacks: Vec<_> = elf.snacks().iter().map(u32::to_string).collect();
+            println!("snacks: {}", snacks.join(" "));
+            println!("total: {}", elf.total());
+            println!("rank: {} of {}", rank, inventory.len());
+            Ok(())
+        }
+        _ => anyhow::bail!("There are only {} elves.", inventory.len()),
+    }
+}
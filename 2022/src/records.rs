@@ -0,0 +1,74 @@
+//! Helpers for record-based inputs: paragraphs separated by blank lines, and one number per
+//! line.
+//!
+//! Line numbers passed in and reported in errors are 1-based, so that errors can point at
+//! the input file directly.
+
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq)]
+pub enum ParseNumberError {
+    Invalid { line: usize, text: String },
+    Overflow { line: usize, text: String },
+}
+
+impl std::error::Error for ParseNumberError {}
+
+impl std::fmt::Display for ParseNumberError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseNumberError::Invalid { line, text } => {
+                write!(f, "line {}: `{}` is not a number", line, text)
+            }
+            ParseNumberError::Overflow { line, text } => {
+                write!(f, "line {}: `{}` is out of range", line, text)
+            }
+        }
+    }
+}
+
+/// Groups lines into paragraphs separated by one or more blank lines, along with the line
+/// number each paragraph starts on.
+pub fn paragraphs<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+) -> impl Iterator<Item = (usize, Vec<&'a str>)> {
+    let mut lines = lines.into_iter().enumerate().peekable();
+    std::iter::from_fn(move || {
+        while lines.next_if(|(_, line)| line.trim().is_empty()).is_some() {}
+        let (first, line) = lines.next()?;
+        let mut paragraph = vec![line];
+        while let Some((_, line)) = lines.next_if(|(_, line)| !line.trim().is_empty()) {
+            paragraph.push(line);
+        }
+        Some((first + 1, paragraph))
+    })
+}
+
+/// Parses one integer per line from `lines` starting on line `first_line`.
+pub fn parse_numbers<T, S>(first_line: usize, lines: &[S]) -> Result<Vec<T>, ParseNumberError>
+where
+    T: FromStr<Err = ParseIntError>,
+    S: AsRef<str>,
+{
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line_number = first_line + i;
+            let text = line.as_ref().trim();
+            text.parse().map_err(|e: ParseIntError| match e.kind() {
+                IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+                    ParseNumberError::Overflow {
+                        line: line_number,
+                        text: text.to_string(),
+                    }
+                }
+                _ => ParseNumberError::Invalid {
+                    line: line_number,
+                    text: text.to_string(),
+                },
+            })
+        })
+        .collect()
+}
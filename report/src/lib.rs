@@ -0,0 +1,302 @@
+//! Running the solvers of any year and reporting their answers, either as bare answers, as
+//! JSON for dashboards and for diffing runs, or as a table for reading.
+
+use std::fmt::{Display, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// How to report the answers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// One answer per line, and nothing else.
+    Plain,
+    /// An array with one object per solver.
+    Json,
+    /// One aligned row per solver, under a header.
+    Table,
+}
+
+#[derive(Debug)]
+pub struct UnknownFormatError {
+    name: String,
+}
+
+impl std::error::Error for UnknownFormatError {}
+
+impl std::fmt::Display for UnknownFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown format `{}`: expected `plain`, `json` or `table`",
+            self.name
+        )
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = UnknownFormatError;
+
+    fn from_str(s: &str) -> Result<Format, UnknownFormatError> {
+        match s {
+            "plain" => Ok(Format::Plain),
+            "json" => Ok(Format::Json),
+            "table" => Ok(Format::Table),
+            _ => Err(UnknownFormatError {
+                name: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// The result of running one part of one day's puzzle.
+#[derive(Debug)]
+pub struct Outcome {
+    pub year: u32,
+    pub day: u32,
+    pub part: u32,
+    /// The answer, or the message of the error the solver failed with.
+    pub answer: Result<String, String>,
+    pub duration: Duration,
+}
+
+impl Outcome {
+    pub fn status(&self) -> &'static str {
+        match self.answer {
+            Ok(_) => "ok",
+            Err(_) => "error",
+        }
+    }
+}
+
+/// Runs `solver`, timing it and capturing its answer or error. A solver which panics fails
+/// with the panic's message, so that the others still run and get reported.
+pub fn run<T: Display>(
+    year: u32,
+    day: u32,
+    part: u32,
+    solver: impl FnOnce() -> anyhow::Result<T>,
+) -> Outcome {
+    let start = Instant::now();
+    let answer = panic::catch_unwind(AssertUnwindSafe(solver));
+    let duration = start.elapsed();
+    let answer = match answer {
+        Ok(answer) => answer
+            .map(|a| a.to_string())
+            .map_err(|e| format!("{:#}", e)),
+        Err(payload) => Err(format!("panicked: {}", panic_message(&*payload))),
+    };
+    Outcome {
+        year,
+        day,
+        part,
+        answer,
+        duration,
+    }
+}
+
+/// The message a panic was raised with, which `panic!` makes either a `&str` or a `String`.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown payload"
+    }
+}
+
+/// Formats `outcomes` as `format`. Plain output leaves out the solvers which failed, so
+/// that it only ever holds answers.
+pub fn render(format: Format, outcomes: &[Outcome]) -> String {
+    match format {
+        Format::Plain => outcomes
+            .iter()
+            .filter_map(|outcome| outcome.answer.as_ref().ok())
+            .map(|answer| format!("{}\n", answer))
+            .collect(),
+        Format::Json => render_json(outcomes),
+        Format::Table => render_table(outcomes),
+    }
+}
+
+fn render_json(outcomes: &[Outcome]) -> String {
+    let objects = outcomes
+        .iter()
+        .map(|outcome| {
+            let (answer, error) = match &outcome.answer {
+                Ok(answer) => (json_string(answer), "null".to_string()),
+                Err(error) => ("null".to_string(), json_string(error)),
+            };
+            format!(
+                "  {{\"year\":{},\"day\":{},\"part\":{},\"answer\":{},\"duration_us\":{},\"status\":\"{}\",\"error\":{}}}",
+                outcome.year,
+                outcome.day,
+                outcome.part,
+                answer,
+                outcome.duration.as_micros(),
+                outcome.status(),
+                error
+            )
+        })
+        .collect::<Vec<_>>();
+    if objects.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
+    }
+}
+
+/// `s` as a JSON string literal.
+fn json_string(s: &str) -> String {
+    let mut literal = String::with_capacity(s.len() + 2);
+    literal.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_control() => write!(literal, "\\u{:04x}", c as u32).unwrap(),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn render_table(outcomes: &[Outcome]) -> String {
+    let header = ["year", "day", "part", "answer", "duration", "status"];
+    let rows = outcomes
+        .iter()
+        .map(|outcome| {
+            let (answer, status) = match &outcome.answer {
+                Ok(answer) => (answer.clone(), outcome.status().to_string()),
+                Err(error) => ("-".to_string(), format!("{}: {}", outcome.status(), error)),
+            };
+            [
+                outcome.year.to_string(),
+                outcome.day.to_string(),
+                outcome.part.to_string(),
+                answer,
+                format!("{:.3} ms", outcome.duration.as_secs_f64() * 1000.0),
+                status,
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    let header = header.map(str::to_string);
+    for row in std::iter::once(&header).chain(&rows) {
+        // Numbers are right-aligned; the status, which is last, is left unpadded.
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .take(header.len() - 1)
+            .map(|(cell, width)| format!("{:>1$}", cell, width))
+            .chain(std::iter::once(row[header.len() - 1].clone()))
+            .collect::<Vec<_>>();
+        writeln!(table, "{}", cells.join("  ")).unwrap();
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcomes() -> Vec<Outcome> {
+        vec![
+            Outcome {
+                year: 2020,
+                day: 1,
+                part: 1,
+                answer: Ok("1005459".to_string()),
+                duration: Duration::from_micros(1250),
+            },
+            Outcome {
+                year: 2020,
+                day: 10,
+                part: 2,
+                answer: Err("No such file \"input_day10.txt\"".to_string()),
+                duration: Duration::from_micros(3),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("json".parse::<Format>().unwrap(), Format::Json);
+        assert_eq!("table".parse::<Format>().unwrap(), Format::Table);
+        assert!("yaml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let outcome = run(2020, 3, 2, || Ok(42));
+        assert_eq!(outcome.answer, Ok("42".to_string()));
+        assert_eq!(outcome.status(), "ok");
+
+        let outcome = run(2020, 3, 2, || -> anyhow::Result<u32> {
+            anyhow::bail!("no map")
+        });
+        assert_eq!(outcome.answer, Err("no map".to_string()));
+        assert_eq!(outcome.status(), "error");
+    }
+
+    #[test]
+    fn test_run_panic() {
+        let outcome = run(2020, 8, 1, || -> anyhow::Result<u32> { panic!("bad jump") });
+        assert_eq!(outcome.answer, Err("panicked: bad jump".to_string()));
+        assert_eq!(outcome.status(), "error");
+
+        let outcome = run(2020, 12, 1, || -> anyhow::Result<u32> {
+            let offset = i32::MAX;
+            panic!("offset {} overflowed", offset)
+        });
+        assert_eq!(
+            outcome.answer,
+            Err("panicked: offset 2147483647 overflowed".to_string())
+        );
+
+        let outcome = run(2020, 13, 2, || -> anyhow::Result<u32> {
+            std::panic::panic_any(13)
+        });
+        assert_eq!(outcome.answer, Err("panicked: unknown payload".to_string()));
+    }
+
+    #[test]
+    fn test_render_plain() {
+        assert_eq!(render(Format::Plain, &outcomes()), "1005459\n");
+    }
+
+    #[test]
+    fn test_render_json() {
+        assert_eq!(
+            render(Format::Json, &outcomes()),
+            "[\n\
+             \x20 {\"year\":2020,\"day\":1,\"part\":1,\"answer\":\"1005459\",\"duration_us\":1250,\"status\":\"ok\",\"error\":null},\n\
+             \x20 {\"year\":2020,\"day\":10,\"part\":2,\"answer\":null,\"duration_us\":3,\"status\":\"error\",\"error\":\"No such file \\\"input_day10.txt\\\"\"}\n\
+             ]\n"
+        );
+        assert_eq!(render(Format::Json, &[]), "[]\n");
+        assert_eq!(json_string("a\tb\u{1}"), "\"a\\tb\\u0001\"");
+    }
+
+    #[test]
+    fn test_render_table() {
+        assert_eq!(
+            render(Format::Table, &outcomes()),
+            "year  day  part   answer  duration  status\n\
+             2020    1     1  1005459  1.250 ms  ok\n\
+             2020   10     2        -  0.003 ms  error: No such file \"input_day10.txt\"\n"
+        );
+    }
+}